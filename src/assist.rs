@@ -0,0 +1,196 @@
+use crate::{kinematics::rudder_for_curvature, pose::normalize, PM1, TARGET_MEMORY_TIMEOUT};
+use pm1_control_model::Physical;
+use std::time::Instant;
+
+/// 航向误差到角速度的增益（1/s）
+const HEADING_GAIN: f32 = 1.5;
+/// 航向保持时的最大角速度（rad/s）
+const MAX_YAW_RATE: f32 = 0.8;
+/// 速度低于此值（m/s）时不调整后轮
+const MIN_SPEED: f32 = 0.02;
+/// 定速巡航的积分增益（1/s）
+const CRUISE_GAIN: f32 = 0.5;
+/// 定速巡航对目标速度的最大修正（m/s）
+const MAX_CORRECTION: f32 = 0.2;
+/// 发出的速度比修正后的速度小于此值（m/s）以上时视为被下游截断
+const CLIP_TOLERANCE: f32 = 1e-3;
+
+/// 航向保持：操作者给出速度和期望航向（或航向变化率），由控制器调整后轮
+pub(crate) struct HeadingHold {
+    speed: f32,
+    reference: f32,
+    rate: f32,
+    deadline: Instant,
+    last: Instant,
+}
+
+/// 定速巡航：根据里程计测得的速度修正目标速度，补偿负载变化
+#[derive(Default)]
+pub(crate) struct Cruise {
+    pub enabled: bool,
+    measured: f32,
+    odometry: Option<Instant>,
+    integral: f32,
+    last: Option<Instant>,
+    /// 本周期修正后的速度和积分增量
+    applied: Option<(f32, f32)>,
+}
+
+impl Cruise {
+    /// 用一次里程计增量更新测得的速度
+    pub fn update(&mut self, time: Instant, distance: f32) {
+        if let Some(last) = self.odometry.replace(time) {
+            if time > last {
+                self.measured = distance / (time - last).as_secs_f32();
+            }
+        }
+    }
+
+    /// 修正目标速度，目标为零或换向时清除积分
+    pub fn correct(&mut self, time: Instant, speed: f32) -> f32 {
+        let dt = self
+            .last
+            .replace(time)
+            .map_or(0.0, |last| (time - last).as_secs_f32());
+        self.applied = None;
+        if !self.enabled || speed == 0.0 || speed * self.integral < 0.0 {
+            self.integral = 0.0;
+            return speed;
+        }
+        let max = MAX_CORRECTION / CRUISE_GAIN;
+        let integral = (self.integral + (speed - self.measured) * dt).clamp(-max, max);
+        let step = integral - self.integral;
+        self.integral = integral;
+        let corrected = speed + CRUISE_GAIN * self.integral;
+        // 修正不能改变方向
+        let corrected = if corrected * speed > 0.0 {
+            corrected
+        } else {
+            0.0
+        };
+        self.applied = Some((corrected, step));
+        corrected
+    }
+
+    /// 用实际发出的速度 `sent` 检查本周期的修正。
+    ///
+    /// 安全处理或运行时限制截断了修正后的速度时，测得的速度达不到目标，
+    /// 撤销本周期继续加大修正的积分，避免积分饱和。
+    pub fn limited(&mut self, sent: f32) {
+        if let Some((corrected, step)) = self.applied.take() {
+            if sent.abs() < corrected.abs() - CLIP_TOLERANCE && step * corrected > 0.0 {
+                self.integral -= step;
+            }
+        }
+    }
+}
+
+impl PM1 {
+    /// 以 `speed` 行驶并保持里程计坐标系下的航向 `heading`。
+    ///
+    /// 与 `drive` 一样需要持续调用，超时后停止。
+    pub fn hold_heading(&mut self, speed: f32, heading: f32) {
+        let now = Instant::now();
+        let last = self.heading_hold.as_ref().map_or(now, |h| h.last);
+        self.heading_hold = Some(HeadingHold {
+            speed,
+            reference: heading,
+            rate: 0.0,
+            deadline: now + TARGET_MEMORY_TIMEOUT,
+            last,
+        });
+    }
+
+    /// 以 `speed` 行驶，航向以 `rate`（rad/s，逆时针为正）变化，`rate` 为零时保持当前航向。
+    ///
+    /// 与 `drive` 一样需要持续调用，超时后停止。
+    pub fn steer_heading(&mut self, speed: f32, rate: f32) {
+        let now = Instant::now();
+        let (reference, last) = self
+            .heading_hold
+            .as_ref()
+            .map_or((self.pose.theta, now), |h| (h.reference, h.last));
+        self.heading_hold = Some(HeadingHold {
+            speed,
+            reference,
+            rate,
+            deadline: now + TARGET_MEMORY_TIMEOUT,
+            last,
+        });
+    }
+
+    /// 开启或关闭定速巡航，修正在电量、围栏和障碍物处理之前施加，对所有控制目标生效
+    #[inline]
+    pub fn set_cruise(&mut self, enabled: bool) {
+        self.cruise.enabled = enabled;
+    }
+
+    #[inline]
+    pub fn cruise(&self) -> bool {
+        self.cruise.enabled
+    }
+
+    /// 每个控制周期根据航向误差更新控制目标
+    pub(crate) fn heading_control(&mut self, time: Instant) {
+        let hold = match &mut self.heading_hold {
+            Some(h) if time < h.deadline => h,
+            Some(_) => {
+                self.heading_hold = None;
+                return;
+            }
+            None => return,
+        };
+        let dt = time.saturating_duration_since(hold.last).as_secs_f32();
+        hold.last = time;
+        hold.reference = normalize(hold.reference + hold.rate * dt);
+        let speed = hold.speed;
+        let rudder = if speed.abs() < MIN_SPEED {
+            f32::NAN
+        } else {
+            let yaw_rate = (HEADING_GAIN * normalize(hold.reference - self.pose.theta))
+                .clamp(-MAX_YAW_RATE, MAX_YAW_RATE);
+            rudder_for_curvature(&self.model, yaw_rate / speed)
+        };
+        self.set_target((time, Physical { speed, rudder }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 测得的速度一直为零时，每个周期修正一次并返回修正后的速度
+    fn run(cruise: &mut Cruise, start: Instant, periods: u64, sent: impl Fn(f32) -> f32) -> f32 {
+        let mut corrected = 0.0;
+        for i in 0..periods {
+            let time = start + Duration::from_millis(40 * i);
+            cruise.update(time, 0.0);
+            corrected = cruise.correct(time, 0.5);
+            cruise.limited(sent(corrected));
+        }
+        corrected
+    }
+
+    #[test]
+    fn integrate_when_not_clipped() {
+        let mut cruise = Cruise {
+            enabled: true,
+            ..Default::default()
+        };
+        let corrected = run(&mut cruise, Instant::now(), 100, |speed| speed);
+        assert!((corrected - 0.5 - MAX_CORRECTION).abs() < 1e-4);
+    }
+
+    #[test]
+    fn freeze_when_clipped() {
+        let mut cruise = Cruise {
+            enabled: true,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        // 下游把速度截断到 0.3m/s，积分不再增长
+        let corrected = run(&mut cruise, start, 100, |speed| speed.min(0.3));
+        assert!(corrected < 0.5 + MAX_CORRECTION / 4.0);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{PM1Event, PM1};
+use driver::Driver;
+use pm1_control_model::Physical;
+use std::time::Instant;
+
+/// 自动驾驶任务结束的原因
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AutopilotResult {
+    /// 正常完成
+    Finished,
+    /// 未能在限定时间内完成
+    TimedOut,
+    /// 被 `stop` 或 `cancel_autopilot` 取消
+    Cancelled,
+    /// 任务自身判断无法继续
+    Failed,
+}
+
+/// 自动驾驶任务在一个控制周期中的决定
+#[derive(Clone, Copy, Debug)]
+pub enum Step {
+    /// 以此为本周期的控制目标
+    Drive(Physical),
+    /// 任务结束，底盘按 `command` 停止策略停止
+    Finish(AutopilotResult),
+}
+
+/// 每个控制周期生成一次控制目标的自动驾驶任务。
+///
+/// 任务的目标与 `set_target` 设置的目标一样经过电量、围栏、障碍物和运行时限制的处理。
+pub trait Autopilot: Send {
+    fn step(&mut self, time: Instant, pm1: &PM1) -> Step;
+
+    /// 每次 `step` 之后调用，返回需要报告的进度（0 到 1），
+    /// 产生 `PM1Event::AutopilotProgress` 事件
+    #[inline]
+    fn progress(&mut self) -> Option<f32> {
+        None
+    }
+}
+
+impl PM1 {
+    /// 开始执行自动驾驶任务，返回被替换的任务，同时取消正在执行的航点任务。
+    ///
+    /// 任务结束时产生 `PM1Event::AutopilotFinished` 事件，
+    /// 被替换的任务产生 `AutopilotResult::Cancelled`。
+    #[inline]
+    pub fn start_autopilot(
+        &mut self,
+        autopilot: impl Autopilot + 'static,
+    ) -> Option<Box<dyn Autopilot>> {
+        let now = Instant::now();
+        self.abort_mission(now);
+        let replaced = self.abort_autopilot(now);
+        self.autopilot = Some(Box::new(autopilot));
+        replaced
+    }
+
+    /// 取消正在执行的自动驾驶任务并停止
+    #[inline]
+    pub fn cancel_autopilot(&mut self) {
+        self.stop();
+    }
+
+    #[inline]
+    pub fn autopilot_active(&self) -> bool {
+        self.autopilot.is_some()
+    }
+
+    /// 执行自动驾驶任务直到结束，期间的其他事件交给 `f` 处理。
+    ///
+    /// 连接断开时返回 `None`。
+    pub fn run<F>(
+        &mut self,
+        autopilot: impl Autopilot + 'static,
+        mut f: F,
+    ) -> Option<AutopilotResult>
+    where
+        F: FnMut(&mut Self, (Instant, PM1Event)),
+    {
+        // 被替换的任务先产生一次取消事件
+        let mut replaced = self.start_autopilot(autopilot).is_some();
+        let mut result = None;
+        self.join(|pm1, event| match event {
+            Some(event @ (_, PM1Event::AutopilotFinished(_))) if replaced => {
+                replaced = false;
+                f(pm1, event);
+                true
+            }
+            Some((_, PM1Event::AutopilotFinished(r))) => {
+                result = Some(r);
+                false
+            }
+            Some(event) => {
+                f(pm1, event);
+                true
+            }
+            None => true,
+        });
+        if result.is_none() {
+            self.autopilot = None;
+        }
+        result
+    }
+
+    /// 取消任务并返回被取消的任务，由 `stop` 和 `start_autopilot` 调用
+    pub(crate) fn abort_autopilot(&mut self, time: Instant) -> Option<Box<dyn Autopilot>> {
+        let autopilot = self.autopilot.take();
+        if autopilot.is_some() {
+            self.events.push_back((
+                time,
+                PM1Event::AutopilotFinished(AutopilotResult::Cancelled),
+            ));
+        }
+        autopilot
+    }
+
+    /// 每个控制周期由任务更新控制目标
+    pub(crate) fn autopilot_control(&mut self, time: Instant) {
+        let mut autopilot = match self.autopilot.take() {
+            Some(a) => a,
+            None => return,
+        };
+        match autopilot.step(time, self) {
+            Step::Drive(target) => {
+                self.set_target((time, target));
+                if let Some(progress) = autopilot.progress() {
+                    self.events
+                        .push_back((time, PM1Event::AutopilotProgress(progress)));
+                }
+                self.autopilot = Some(autopilot);
+            }
+            Step::Finish(result) => {
+                self.halt(time);
+                self.events
+                    .push_back((time, PM1Event::AutopilotFinished(result)));
+            }
+        }
+    }
+}
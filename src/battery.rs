@@ -0,0 +1,280 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// 估计放电速率时保留的电量变化次数
+const SEGMENTS: usize = 20;
+/// 平均负载的时间常数
+const LOAD_TAU: Duration = Duration::from_secs(30);
+
+/// 低电量策略，电量不高于阈值时进入对应等级。
+///
+/// 阈值为 0 表示不启用该等级。
+#[derive(Clone, Copy, Debug)]
+pub struct BatteryPolicy {
+    /// 发出警告的电量
+    pub warning: u8,
+    /// 开始限速的电量
+    pub limit: u8,
+    /// 限速等级下的最大速度（m/s）
+    pub limited_speed: f32,
+    /// 安全停止的电量，停止方式由 `StopPolicy::battery` 决定
+    pub stop: u8,
+}
+
+impl Default for BatteryPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            warning: 20,
+            limit: 0,
+            limited_speed: 0.3,
+            stop: 0,
+        }
+    }
+}
+
+/// 电量等级
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum BatteryLevel {
+    Normal,
+    Warning,
+    Limited,
+    Critical,
+}
+
+impl BatteryPolicy {
+    pub fn level(&self, percent: u8) -> BatteryLevel {
+        #[inline]
+        fn below(percent: u8, threshold: u8) -> bool {
+            threshold > 0 && percent <= threshold
+        }
+
+        if below(percent, self.stop) {
+            BatteryLevel::Critical
+        } else if below(percent, self.limit) {
+            BatteryLevel::Limited
+        } else if below(percent, self.warning) {
+            BatteryLevel::Warning
+        } else {
+            BatteryLevel::Normal
+        }
+    }
+}
+
+/// 电量监视和剩余时间估计。
+///
+/// 每次电量变化时记录经过的时间和行驶里程，
+/// 用最小二乘拟合 `放电速率 = a + b * 平均速度`，再代入近期的平均速度估计剩余时间。
+pub(crate) struct BatteryMonitor {
+    pub policy: BatteryPolicy,
+    level: Option<BatteryLevel>,
+
+    percent: u8,
+    last: Option<(Instant, u8, f32)>,
+    segments: VecDeque<(f32, f32, f32)>,
+
+    distance: f32,
+    load: f32,
+    load_time: Option<Instant>,
+}
+
+impl BatteryMonitor {
+    pub fn new() -> Self {
+        Self {
+            policy: Default::default(),
+            level: None,
+
+            percent: 0,
+            last: None,
+            segments: VecDeque::with_capacity(SEGMENTS),
+
+            distance: 0.0,
+            load: 0.0,
+            load_time: None,
+        }
+    }
+
+    /// 尚未收到电量时视为正常
+    #[inline]
+    pub fn level(&self) -> BatteryLevel {
+        self.level.unwrap_or(BatteryLevel::Normal)
+    }
+
+    /// 更新电量，等级发生变化时返回新等级
+    pub fn update(&mut self, time: Instant, percent: u8) -> Option<BatteryLevel> {
+        match self.last {
+            Some((t, p, d)) if percent < p => {
+                self.segments.push_back((
+                    (time - t).as_secs_f32(),
+                    (p - percent) as f32,
+                    self.distance - d,
+                ));
+                if self.segments.len() > SEGMENTS {
+                    self.segments.pop_front();
+                }
+                self.last = Some((time, percent, self.distance));
+            }
+            Some((_, p, _)) if percent == p => {}
+            // 首次收到或正在充电，重新开始记录
+            _ => {
+                self.segments.clear();
+                self.last = Some((time, percent, self.distance));
+            }
+        }
+        self.percent = percent;
+
+        let level = self.policy.level(percent);
+        if self.level.replace(level) != Some(level) {
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    /// 重新按照策略计算等级
+    #[inline]
+    pub fn refresh(&mut self) -> Option<BatteryLevel> {
+        self.level?;
+        let level = self.policy.level(self.percent);
+        if self.level.replace(level) != Some(level) {
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    /// 累计行驶里程，更新平均负载
+    pub fn update_motion(&mut self, time: Instant, distance: f32) {
+        let distance = distance.abs();
+        self.distance += distance;
+        if let Some(last) = self.load_time.replace(time) {
+            let dt = (time - last).as_secs_f32();
+            if dt > 0.0 {
+                let k = (dt / LOAD_TAU.as_secs_f32()).min(1.0);
+                self.load += (distance / dt - self.load) * k;
+            }
+        }
+    }
+
+    /// 估计剩余运行时间
+    pub fn remaining(&self) -> Option<Duration> {
+        // 加权最小二乘：rate = a + b * load，权重为时长
+        let (mut sw, mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (dt, dp, dd) in &self.segments {
+            let (x, y) = (dd / dt, dp / dt);
+            sw += dt;
+            sx += dt * x;
+            sy += dt * y;
+            sxx += dt * x * x;
+            sxy += dt * x * y;
+        }
+        if sw == 0.0 {
+            return None;
+        }
+        let det = sw * sxx - sx * sx;
+        let rate = if det.abs() > 1e-6 * sw * sw {
+            let b = ((sw * sxy - sx * sy) / det).max(0.0);
+            let a = (sy - b * sx) / sw;
+            a + b * self.load
+        } else {
+            sy / sw
+        };
+        if rate > 0.0 {
+            // 速率接近零时时长会溢出
+            Duration::try_from_secs_f32(self.percent as f32 / rate).ok()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn level_thresholds() {
+        let policy = BatteryPolicy {
+            warning: 30,
+            limit: 20,
+            limited_speed: 0.3,
+            stop: 10,
+        };
+        assert_eq!(policy.level(50), BatteryLevel::Normal);
+        assert_eq!(policy.level(30), BatteryLevel::Warning);
+        assert_eq!(policy.level(20), BatteryLevel::Limited);
+        assert_eq!(policy.level(10), BatteryLevel::Critical);
+        assert_eq!(policy.level(0), BatteryLevel::Critical);
+    }
+
+    #[test]
+    fn zero_threshold_disabled() {
+        let policy = BatteryPolicy::default();
+        assert_eq!(policy.level(0), BatteryLevel::Warning);
+        let policy = BatteryPolicy {
+            warning: 0,
+            ..policy
+        };
+        assert_eq!(policy.level(0), BatteryLevel::Normal);
+    }
+
+    #[test]
+    fn level_changes() {
+        let mut monitor = BatteryMonitor::new();
+        let t0 = Instant::now();
+        assert_eq!(monitor.level(), BatteryLevel::Normal);
+        assert_eq!(monitor.update(t0, 50), Some(BatteryLevel::Normal));
+        assert_eq!(monitor.update(t0 + SECOND, 50), None);
+        assert_eq!(
+            monitor.update(t0 + SECOND * 2, 20),
+            Some(BatteryLevel::Warning)
+        );
+        monitor.policy.warning = 10;
+        assert_eq!(monitor.refresh(), Some(BatteryLevel::Normal));
+    }
+
+    #[test]
+    fn remaining_without_motion() {
+        let mut monitor = BatteryMonitor::new();
+        let t0 = Instant::now();
+        monitor.update(t0, 100);
+        assert_eq!(monitor.remaining(), None);
+        monitor.update(t0 + SECOND * 60, 99);
+        monitor.update(t0 + SECOND * 120, 98);
+        // 每 60s 下降 1%
+        let remaining = monitor.remaining().unwrap().as_secs_f32();
+        assert!((remaining - 98.0 * 60.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn remaining_depends_on_load() {
+        let mut monitor = BatteryMonitor::new();
+        let t0 = Instant::now();
+        monitor.update(t0, 100);
+        // 静止 100s 下降 1%
+        monitor.update_motion(t0 + SECOND * 100, 0.0);
+        monitor.update(t0 + SECOND * 100, 99);
+        // 以 1m/s 行驶 50s 下降 1%
+        monitor.update_motion(t0 + SECOND * 150, 50.0);
+        monitor.update(t0 + SECOND * 150, 98);
+        // rate = 0.01 + 0.01 * load
+        monitor.load = 0.5;
+        let remaining = monitor.remaining().unwrap().as_secs_f32();
+        assert!((remaining - 98.0 / 0.015).abs() < 1.0);
+    }
+
+    #[test]
+    fn charging_restarts_estimate() {
+        let mut monitor = BatteryMonitor::new();
+        let t0 = Instant::now();
+        monitor.update(t0, 90);
+        monitor.update(t0 + SECOND * 60, 89);
+        assert!(monitor.remaining().is_some());
+        monitor.update(t0 + SECOND * 120, 95);
+        assert_eq!(monitor.remaining(), None);
+    }
+}
@@ -0,0 +1,33 @@
+use pm1_control_model::Pm1Model;
+use pm1_sdk::{identify, read_trace, CalibrationStore};
+use std::{fs::File, io::BufReader};
+
+/// 用法：`pm1-identify <记录文件> [底盘名称]`，给出名称时使用该底盘的校准参数
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: pm1-identify <trace> [name]");
+            return;
+        }
+    };
+    let mut model = Pm1Model::default();
+    if let Some(c) = args
+        .next()
+        .and_then(|name| CalibrationStore::global().ok()?.get(&name))
+    {
+        c.apply_to(&mut model);
+    }
+    let records = match File::open(&path).and_then(|f| read_trace(BufReader::new(f))) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            return;
+        }
+    };
+    match identify(&records, &model) {
+        Some(result) => println!("{}", result),
+        None => eprintln!("Not enough samples."),
+    }
+}
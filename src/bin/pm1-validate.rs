@@ -0,0 +1,91 @@
+use pm1_control_model::Pm1Model;
+use pm1_sdk::{read_trace, validate, CalibrationStore, Dynamics, Limits};
+use std::{fs::File, io::BufReader};
+
+const USAGE: &str =
+    "Usage: pm1-validate <trace> [name] [--dynamics <a> <b> <rudder_rate>] [--limit <field>=<value>]...";
+
+/// 用法：`pm1-validate <记录文件> [底盘名称] [--dynamics a b 转向角速度] [--limit 字段=值]...`
+///
+/// 给出名称时使用该底盘的校准参数；记录中没有运行时限制，
+/// 需要用 `--limit` 逐项给出采集时的 `Limits`，例如 `--limit max_forward=0.5`。
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    let path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+    let mut model = Pm1Model::default();
+    if let Some(name) = args.next_if(|arg| !arg.starts_with("--")) {
+        match CalibrationStore::global().map(|store| store.get(&name)) {
+            Ok(Some(c)) => c.apply_to(&mut model),
+            Ok(None) => eprintln!("No calibration for {}, using default model.", name),
+            Err(e) => eprintln!("Failed to open calibration store: {}", e),
+        }
+    }
+    let mut dynamics = Dynamics::default();
+    let mut limits = Limits::default();
+    while let Some(arg) = args.next() {
+        let parsed = match arg.as_str() {
+            "--dynamics" => {
+                let values = args
+                    .by_ref()
+                    .take(3)
+                    .map(|s| s.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>();
+                match values.as_deref() {
+                    Ok(&[a, b, rate]) => {
+                        dynamics.optimizer = (a, b);
+                        dynamics.rudder_rate = rate;
+                        dynamics.is_valid()
+                    }
+                    _ => false,
+                }
+            }
+            "--limit" => args
+                .next()
+                .is_some_and(|limit| parse_limit(&mut limits, &limit)),
+            _ => false,
+        };
+        if !parsed {
+            eprintln!("Invalid argument: {}", arg);
+            eprintln!("{}", USAGE);
+            return;
+        }
+    }
+    let records = match File::open(&path).and_then(|f| read_trace(BufReader::new(f))) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            return;
+        }
+    };
+    print!("{}", validate(&records, &model, dynamics, limits));
+}
+
+/// 解析 `字段=值` 形式的一项限制
+fn parse_limit(limits: &mut Limits, arg: &str) -> bool {
+    let (field, value) = match arg.split_once('=') {
+        Some((field, value)) => match value.parse() {
+            Ok(value) => (field, value),
+            Err(_) => return false,
+        },
+        None => return false,
+    };
+    let slot = match field {
+        "max_forward" => &mut limits.max_forward,
+        "max_backward" => &mut limits.max_backward,
+        "max_rudder" => &mut limits.max_rudder,
+        "rudder_rate" => &mut limits.rudder_rate,
+        "acceleration" => &mut limits.acceleration,
+        "deceleration" => &mut limits.deceleration,
+        "lateral_acceleration" => &mut limits.lateral_acceleration,
+        "yaw_rate" => &mut limits.yaw_rate,
+        _ => return false,
+    };
+    *slot = value;
+    true
+}
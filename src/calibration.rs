@@ -0,0 +1,455 @@
+use crate::{kinematics::unit_wheels, PM1Event, PM1};
+use pm1_control_model::{Physical, Pm1Model, Wheels};
+use std::{
+    collections::HashMap,
+    f32::consts::TAU,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+
+/// 未设置存储路径时从此环境变量读取
+const STORE_ENV: &str = "PM1_CALIBRATION";
+/// 未设置默认底盘名称时从此环境变量读取
+const NAME_ENV: &str = "PM1_NAME";
+
+/// 校准开始后，先行驶此距离（m）等待后轮和速度稳定
+const SETTLE_DISTANCE: f32 = 0.3;
+/// 校准时用于统计的行驶距离（m）
+const MEASURE_DISTANCE: f32 = 2.0;
+/// 校准时的行驶速度（m/s）
+const CALIBRATION_SPEED: f32 = 0.2;
+/// 后轮零偏的搜索范围（rad）
+const MAX_TRIM: f32 = 0.5;
+
+lazy_static::lazy_static! {
+    static ref STORE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+    static ref DEFAULT_NAME: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// 一台底盘的校准参数
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Calibration {
+    /// 后轮零偏（rad），实际角度 = 编码器角度 + 零偏
+    pub rudder_trim: f32,
+    /// 覆盖 `Pm1Model::wheel` 的有效轮半径（m）
+    pub wheel: Option<f32>,
+    /// 覆盖 `Pm1Model::width` 的有效轮距（m）
+    pub width: Option<f32>,
+}
+
+impl Calibration {
+    /// 用校准过的参数覆盖模型
+    #[inline]
+    pub fn apply_to(&self, model: &mut Pm1Model) {
+        if let Some(wheel) = self.wheel {
+            model.wheel = wheel;
+        }
+        if let Some(width) = self.width {
+            model.width = width;
+        }
+    }
+}
+
+/// 以底盘名称为键保存校准参数的文件。
+///
+/// 每行一台底盘：`名称 后轮零偏 [轮半径 轮距]`，名称中不能包含空白字符，
+/// 未校准的模型参数写作 `-`。
+///
+/// 串口可能更换，因此校准参数按底盘名称而不是串口查找：
+/// 构造 `PM1` 时使用 `PM1::default_name`（或环境变量 `PM1_NAME`）给出的名称，
+/// 未设置默认名称时不加载校准参数，需要在连接后调用 `PM1::set_name`。
+pub struct CalibrationStore {
+    path: PathBuf,
+    entries: HashMap<String, Calibration>,
+}
+
+impl CalibrationStore {
+    /// 设置全局存储路径，`PM1` 构造时按默认底盘名称从此处加载校准参数
+    pub fn set_path(path: impl Into<PathBuf>) {
+        *STORE_PATH.lock().unwrap() = Some(path.into());
+    }
+
+    /// 全局存储路径，未设置时读取环境变量 `PM1_CALIBRATION`
+    pub fn path() -> Option<PathBuf> {
+        STORE_PATH
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| std::env::var_os(STORE_ENV).map(PathBuf::from))
+    }
+
+    /// 打开全局存储
+    #[inline]
+    pub fn global() -> io::Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "calibration store path is not set",
+            )),
+        }
+    }
+
+    /// 加载文件，文件不存在时视为空
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut entries = HashMap::new();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap();
+            let rudder_trim = fields
+                .next()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| invalid(line))?;
+            let mut optional = || match fields.next() {
+                None | Some("-") => Ok(None),
+                Some(s) => s.parse().map(Some).map_err(|_| invalid(line)),
+            };
+            let wheel = optional()?;
+            let width = optional()?;
+            entries.insert(
+                name.into(),
+                Calibration {
+                    rudder_trim,
+                    wheel,
+                    width,
+                },
+            );
+        }
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut names = self.entries.keys().collect::<Vec<_>>();
+        names.sort();
+        let text = names
+            .into_iter()
+            .map(|name| {
+                #[inline]
+                fn optional(value: Option<f32>) -> String {
+                    value.map_or_else(|| "-".into(), |v| v.to_string())
+                }
+                let c = &self.entries[name];
+                format!(
+                    "{} {} {} {}\n",
+                    name,
+                    c.rudder_trim,
+                    optional(c.wheel),
+                    optional(c.width)
+                )
+            })
+            .collect::<String>();
+        fs::write(&self.path, text)
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<Calibration> {
+        self.entries.get(name).copied()
+    }
+
+    /// 添加或替换一台底盘的校准参数，名称不合法时返回错误
+    pub fn insert(&mut self, name: &str, calibration: Calibration) -> io::Result<()> {
+        if !valid_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid chassis name: {:?}", name),
+            ));
+        }
+        self.entries.insert(name.into(), calibration);
+        Ok(())
+    }
+}
+
+/// 底盘名称非空，不含空白字符和路径分隔符，也不是 `.` 或 `..`
+pub(crate) fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '/' || c == '\\')
+}
+
+#[inline]
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid calibration: {}", line),
+    )
+}
+
+/// 后轮零偏校准过程：低速直行，根据左右轮转角之比推算后轮实际角度
+pub(crate) struct RudderCalibration {
+    travelled: f32,
+    wheels: (f32, f32),
+}
+
+impl RudderCalibration {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            travelled: 0.0,
+            wheels: (0.0, 0.0),
+        }
+    }
+
+    #[inline]
+    pub fn target(&self) -> Physical {
+        Physical {
+            speed: CALIBRATION_SPEED,
+            rudder: 0.0,
+        }
+    }
+
+    /// 累计一次里程计增量，`distance` 为行驶距离
+    pub fn update(&mut self, wheels: Wheels, distance: f32) {
+        if self.travelled >= SETTLE_DISTANCE {
+            self.wheels.0 += wheels.left;
+            self.wheels.1 += wheels.right;
+        }
+        self.travelled += distance.abs();
+    }
+
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.travelled >= SETTLE_DISTANCE + MEASURE_DISTANCE
+    }
+
+    /// 估计指令角度为零时后轮的实际角度
+    pub fn estimate(&self, model: &Pm1Model) -> Option<f32> {
+        let (l, r) = self.wheels;
+        if l + r == 0.0 {
+            return None;
+        }
+        #[inline]
+        fn ratio(w: Wheels) -> f32 {
+            (w.right - w.left) / (w.right + w.left)
+        }
+        // 二分查找使轮速比与测量值一致的后轮角度
+        let target = (r - l) / (r + l);
+        let increasing = ratio(unit_wheels(model, MAX_TRIM)) > ratio(unit_wheels(model, -MAX_TRIM));
+        let (mut lo, mut hi) = (-MAX_TRIM, MAX_TRIM);
+        for _ in 0..32 {
+            let mid = (lo + hi) / 2.0;
+            if (ratio(unit_wheels(model, mid)) < target) == increasing {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Some((lo + hi) / 2.0)
+    }
+}
+
+impl PM1 {
+    /// 底盘名称，用于在存储中查找校准参数和保存的任务；尚未设置时为 `None`
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// 设置默认的底盘名称，之后未指定名称打开的底盘（包括通过 `SupervisorForSingle` 连接的底盘）
+    /// 在构造时使用此名称加载校准参数和保存的任务
+    pub fn set_default_name(name: impl Into<String>) {
+        *DEFAULT_NAME.lock().unwrap() = Some(name.into());
+    }
+
+    /// 默认的底盘名称，未设置时读取环境变量 `PM1_NAME`
+    pub fn default_name() -> Option<String> {
+        DEFAULT_NAME
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| std::env::var(NAME_ENV).ok())
+    }
+
+    /// 设置底盘名称，加载该底盘的校准参数和保存的任务，名称不合法时返回 `false`。
+    ///
+    /// 串口可能更换，因此不会根据串口自动识别底盘；
+    /// 没有默认底盘名称时，需要在连接后调用此方法。
+    pub fn set_name(&mut self, name: &str) -> bool {
+        if !valid_name(name) {
+            return false;
+        }
+        self.name = Some(name.into());
+        if let Some(calibration) = CalibrationStore::global()
+            .ok()
+            .and_then(|store| store.get(name))
+        {
+            self.apply_calibration(calibration);
+        }
+        self.restore_mission();
+        true
+    }
+
+    #[inline]
+    pub fn calibration(&self) -> Calibration {
+        Calibration {
+            rudder_trim: self.rudder_trim,
+            wheel: Some(self.model.wheel),
+            width: Some(self.model.width),
+        }
+    }
+
+    pub fn apply_calibration(&mut self, calibration: Calibration) {
+        self.rudder_trim = calibration.rudder_trim;
+        calibration.apply_to(&mut self.model);
+    }
+
+    /// 开始一次模型参数校准，记录当前的轮子转角。
+    ///
+    /// 之后由操作者驾驶底盘：
+    ///
+    /// - 沿直线行驶一段已知距离，再调用 `finish_distance_calibration`；
+    /// - 或将后轮转到 ±π/2 原地旋转已知圈数，再调用 `finish_spin_calibration`。
+    #[inline]
+    pub fn begin_model_calibration(&mut self) {
+        self.model_calibration = Some(self.wheels_position);
+    }
+
+    /// 根据实际行驶的距离（m）估计有效轮半径，成功时立即应用并返回新的轮半径
+    pub fn finish_distance_calibration(&mut self, distance: f32) -> Option<f32> {
+        let (l, r) = self.model_calibration_delta()?;
+        let rotation = (l.abs() + r.abs()) / 2.0;
+        if rotation == 0.0 || distance <= 0.0 {
+            return None;
+        }
+        self.model.wheel = distance / rotation;
+        Some(self.model.wheel)
+    }
+
+    /// 根据原地旋转的圈数估计有效轮距，成功时立即应用并返回新的轮距。
+    ///
+    /// 后轮处于 ±π/2 时底盘绕前轴中点旋转，转角 = 轮半径 × (右轮转角 - 左轮转角) / 轮距。
+    pub fn finish_spin_calibration(&mut self, turns: f32) -> Option<f32> {
+        let (l, r) = self.model_calibration_delta()?;
+        let angle = turns.abs() * TAU;
+        if angle == 0.0 || l == r {
+            return None;
+        }
+        self.model.width = self.model.wheel * (r - l).abs() / angle;
+        Some(self.model.width)
+    }
+
+    #[inline]
+    fn model_calibration_delta(&mut self) -> Option<(f32, f32)> {
+        let (l0, r0) = self.model_calibration.take()?;
+        let (l, r) = self.wheels_position;
+        Some((l - l0, r - r0))
+    }
+
+    /// 将当前校准参数写入全局存储，需要先设置底盘名称
+    pub fn save_calibration(&self) -> io::Result<()> {
+        let name = self.name.as_deref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "chassis name is not set")
+        })?;
+        let mut store = CalibrationStore::global()?;
+        store.insert(name, self.calibration())?;
+        store.save()
+    }
+
+    /// 开始后轮零偏校准。
+    ///
+    /// 底盘将以低速直行约 2.3m，请确保前方空旷。
+    /// 完成后产生 `PM1Event::RudderCalibrated` 事件并立即应用新的零偏，
+    /// 需要保存时调用 `save_calibration`。
+    #[inline]
+    pub fn start_rudder_calibration(&mut self) {
+        self.rudder_calibration = Some(RudderCalibration::new());
+    }
+
+    #[inline]
+    pub fn cancel_calibration(&mut self) {
+        if self.rudder_calibration.take().is_some() {
+            self.stop();
+        }
+    }
+
+    /// 校准过程中，每个控制周期更新控制目标
+    pub(crate) fn calibration_control(&mut self, time: Instant) {
+        let calibration = match &self.rudder_calibration {
+            Some(c) => c,
+            None => return,
+        };
+        if !calibration.finished() {
+            let target = calibration.target();
+            self.set_target((time, target));
+            return;
+        }
+        if let Some(offset) = calibration.estimate(&self.model) {
+            self.rudder_trim += offset;
+            self.events
+                .push_back((time, PM1Event::RudderCalibrated(self.rudder_trim)));
+        }
+        self.rudder_calibration = None;
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每个测试使用独立的临时文件
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pm1-calibration-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let mut store = CalibrationStore::load(&path).unwrap();
+        assert_eq!(store.get("a"), None);
+        let a = Calibration {
+            rudder_trim: 0.01,
+            wheel: Some(0.1),
+            width: None,
+        };
+        let b = Calibration {
+            rudder_trim: -0.02,
+            wheel: None,
+            width: Some(0.47),
+        };
+        store.insert("a", a).unwrap();
+        store.insert("b", b).unwrap();
+        store.save().unwrap();
+
+        let store = CalibrationStore::load(&path).unwrap();
+        assert_eq!(store.get("a"), Some(a));
+        assert_eq!(store.get("b"), Some(b));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_invalid_names() {
+        let mut store = CalibrationStore::load(temp_path("invalid-names")).unwrap();
+        for name in ["", "a b", "a\tb", "/dev/ttyUSB0", "..", "a\\b"] {
+            assert!(
+                store.insert(name, Default::default()).is_err(),
+                "{:?}",
+                name
+            );
+        }
+        assert!(store.insert("ttyUSB0", Default::default()).is_ok());
+    }
+
+    #[test]
+    fn reject_invalid_lines() {
+        let path = temp_path("invalid-lines");
+        fs::write(&path, "a 0.1 - -\nb x\n").unwrap();
+        let e = CalibrationStore::load(&path).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        fs::write(&path, "a 0.1\n\n  b 0.2 0.1  \n").unwrap();
+        let store = CalibrationStore::load(&path).unwrap();
+        assert_eq!(store.get("a").unwrap().wheel, None);
+        assert_eq!(store.get("b").unwrap().wheel, Some(0.1));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{kinematics::unit_wheels, PM1Event, PM1};
+use pm1_control_model::{Physical, Wheels};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// 指令轮速低于此值（rad/s）视为没有运动指令
+const MOVING: f32 = 0.5;
+/// 测量轮速低于指令的此比例视为堵转
+const STALL_RATIO: f32 = 0.1;
+/// 测量轮速方向与后轮几何关系的偏差（正弦值）超过此值视为打滑
+const SLIP_ERROR: f32 = 0.3;
+/// 停止指令发出后等待减速完成的时间
+const SETTLE: Duration = Duration::from_millis(500);
+/// 异常持续此时间后报告
+const CONFIRM: Duration = Duration::from_millis(800);
+/// 后轮角度误差容限（rad）
+const RUDDER_TOLERANCE: f32 = 0.05;
+/// 后轮到位时间在标称转向时间之外的余量
+const RUDDER_MARGIN: Duration = Duration::from_millis(500);
+/// 统计后轮振荡的时间窗口
+const OSCILLATION_WINDOW: Duration = Duration::from_secs(2);
+/// 时间窗口内误差换向达到此次数视为振荡
+const OSCILLATION_COUNT: usize = 4;
+
+/// 故障类型
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Fault {
+    /// 有运动指令但编码器没有变化
+    Stall,
+    /// 左右轮速与后轮转向几何不一致
+    Slip,
+    /// 没有运动指令但编码器在变化
+    Runaway,
+    /// 后轮未能在预期时间内到达目标角度
+    RudderTracking,
+    /// 后轮在目标角度附近振荡
+    RudderOscillation,
+}
+
+/// 严重程度
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// 诊断结果，故障出现和解除时各报告一次
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub fault: Fault,
+    pub severity: Severity,
+    pub active: bool,
+}
+
+impl Fault {
+    #[inline]
+    pub fn severity(self) -> Severity {
+        match self {
+            Fault::Slip | Fault::RudderOscillation => Severity::Warning,
+            Fault::Stall | Fault::Runaway | Fault::RudderTracking => Severity::Error,
+        }
+    }
+}
+
+/// 异常条件需持续一段时间才确认
+#[derive(Default)]
+struct Debounce {
+    since: Option<Instant>,
+    active: bool,
+}
+
+impl Debounce {
+    /// 更新条件，确认状态发生变化时返回新状态
+    fn update(&mut self, time: Instant, condition: bool) -> Option<bool> {
+        if condition {
+            let since = *self.since.get_or_insert(time);
+            if !self.active && time >= since + CONFIRM {
+                self.active = true;
+                return Some(true);
+            }
+        } else {
+            self.since = None;
+            if self.active {
+                self.active = false;
+                return Some(false);
+            }
+        }
+        None
+    }
+}
+
+/// 比较指令轮速与编码器测量的轮速
+#[derive(Default)]
+pub(crate) struct WheelMonitor {
+    /// 最近一次发出的轮速指令，`None` 表示底盘不受 SDK 控制
+    commanded: Option<Wheels>,
+    rudder: f32,
+    idle_since: Option<Instant>,
+    last: Option<Instant>,
+
+    stall: Debounce,
+    slip: Debounce,
+    runaway: Debounce,
+}
+
+impl WheelMonitor {
+    /// 记录本周期的控制结果，`rudder` 为实际后轮角度
+    pub fn command(&mut self, time: Instant, wheels: Option<Wheels>, rudder: f32) {
+        let moving = wheels.is_some_and(|w| w.left.abs().max(w.right.abs()) >= MOVING);
+        if moving || wheels.is_none() {
+            self.idle_since = None;
+        } else if self.idle_since.is_none() {
+            self.idle_since = Some(time);
+        }
+        self.commanded = wheels;
+        self.rudder = rudder;
+    }
+}
+
+/// 比较后轮目标角度与实际角度
+pub(crate) struct RudderMonitor {
+    target: f32,
+    deadline: Instant,
+    sign: f32,
+    reversals: VecDeque<Instant>,
+
+    tracking: Debounce,
+    oscillation: Debounce,
+}
+
+impl RudderMonitor {
+    #[inline]
+    pub fn new(now: Instant) -> Self {
+        Self {
+            target: f32::NAN,
+            deadline: now,
+            sign: 0.0,
+            reversals: VecDeque::new(),
+
+            tracking: Default::default(),
+            oscillation: Default::default(),
+        }
+    }
+
+    /// 是否存在已确认的后轮故障
+    #[inline]
+    pub fn faulty(&self) -> bool {
+        self.tracking.active || self.oscillation.active
+    }
+}
+
+impl PM1 {
+    /// 用本周期发出的后轮目标和测得的实际角度更新诊断，`target` 为 `None` 表示没有控制
+    pub(crate) fn diagnose_rudder(&mut self, time: Instant, target: Option<f32>, rudder: f32) {
+        let rudder_rate = self.dynamics.rudder_rate;
+        let monitor = &mut self.rudder_monitor;
+        let target = match target {
+            Some(target) => target,
+            None => {
+                // 没有控制时保持已确认的故障，因后轮故障停止后故障一直保持到被确认
+                monitor.target = f32::NAN;
+                monitor.tracking.since = None;
+                monitor.oscillation.since = None;
+                return;
+            }
+        };
+        let error = target - rudder;
+        if monitor.target.is_nan() || (target - monitor.target).abs() >= RUDDER_TOLERANCE {
+            // 目标变化，按标称转向速度重新估计到位时间
+            monitor.target = target;
+            monitor.deadline = time
+                + Duration::try_from_secs_f32(error.abs() / rudder_rate).unwrap_or(RUDDER_MARGIN)
+                + RUDDER_MARGIN;
+            monitor.sign = 0.0;
+            monitor.reversals.clear();
+        }
+        // 记录误差换向
+        if error.abs() > RUDDER_TOLERANCE / 2.0 {
+            let sign = error.signum();
+            if monitor.sign != 0.0 && sign != monitor.sign {
+                monitor.reversals.push_back(time);
+            }
+            monitor.sign = sign;
+        }
+        while let Some(t) = monitor.reversals.front() {
+            if *t + OSCILLATION_WINDOW < time {
+                monitor.reversals.pop_front();
+            } else {
+                break;
+            }
+        }
+        let tracking = time > monitor.deadline && error.abs() > RUDDER_TOLERANCE;
+        let oscillation = monitor.reversals.len() >= OSCILLATION_COUNT;
+        for (fault, debounce, condition) in [
+            (Fault::RudderTracking, &mut monitor.tracking, tracking),
+            (
+                Fault::RudderOscillation,
+                &mut monitor.oscillation,
+                oscillation,
+            ),
+        ] {
+            if let Some(active) = debounce.update(time, condition) {
+                self.events.push_back((
+                    time,
+                    PM1Event::Diagnostic(Diagnostic {
+                        fault,
+                        severity: fault.severity(),
+                        active,
+                    }),
+                ));
+            }
+        }
+    }
+
+    /// 确认后轮故障，清除已确认的故障以恢复控制。
+    ///
+    /// 故障在发出控制时才会解除，因此 `set_stop_on_rudder_fault` 开启后因故障停止时必须调用此方法；
+    /// 故障仍然存在时，恢复控制后会重新诊断出来。
+    pub fn acknowledge_rudder_fault(&mut self) {
+        let now = Instant::now();
+        let monitor = &mut self.rudder_monitor;
+        for (fault, debounce) in [
+            (Fault::RudderTracking, &mut monitor.tracking),
+            (Fault::RudderOscillation, &mut monitor.oscillation),
+        ] {
+            if let Some(active) = debounce.update(now, false) {
+                self.events.push_back((
+                    now,
+                    PM1Event::Diagnostic(Diagnostic {
+                        fault,
+                        severity: fault.severity(),
+                        active,
+                    }),
+                ));
+            }
+        }
+        // 不恢复故障前的控制目标
+        self.target = (now, Physical::RELEASED);
+    }
+
+    /// 用一次里程计增量更新诊断
+    pub(crate) fn diagnose_wheels(&mut self, time: Instant, delta: Wheels) {
+        let monitor = &mut self.wheel_monitor;
+        let dt = match monitor.last.replace(time) {
+            Some(last) if time > last => (time - last).as_secs_f32(),
+            _ => return,
+        };
+        let measured = (delta.left / dt, delta.right / dt);
+        let measured_norm = measured.0.hypot(measured.1);
+        let (stall, slip, runaway) = match monitor.commanded {
+            None => (false, false, false),
+            Some(commanded) => {
+                let norm = commanded.left.hypot(commanded.right);
+                let moving = commanded.left.abs().max(commanded.right.abs()) >= MOVING;
+                let stall = moving && measured_norm < norm * STALL_RATIO;
+                let slip = moving && !stall && {
+                    let unit = unit_wheels(&self.model, monitor.rudder);
+                    let cross = measured.0 * unit.right - measured.1 * unit.left;
+                    cross.abs() > SLIP_ERROR * measured_norm * unit.left.hypot(unit.right)
+                };
+                let runaway = monitor
+                    .idle_since
+                    .is_some_and(|t| time >= t + SETTLE && measured_norm >= MOVING);
+                (stall, slip, runaway)
+            }
+        };
+        for (fault, debounce, condition) in [
+            (Fault::Stall, &mut monitor.stall, stall),
+            (Fault::Slip, &mut monitor.slip, slip),
+            (Fault::Runaway, &mut monitor.runaway, runaway),
+        ] {
+            if let Some(active) = debounce.update(time, condition) {
+                self.events.push_back((
+                    time,
+                    PM1Event::Diagnostic(Diagnostic {
+                        fault,
+                        severity: fault.severity(),
+                        active,
+                    }),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Instant;
+
+/// 急停状态变化
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EStopEvent {
+    /// 急停开关断开
+    Pressed,
+    /// 急停开关恢复闭合
+    Released,
+    /// 急停已被确认，恢复控制
+    Acknowledged,
+}
+
+/// 急停锁存。
+///
+/// 开启锁存时，急停开关恢复后仍保持停止，直到调用 `PM1::acknowledge_estop`。
+pub(crate) struct EStop {
+    pub latch: bool,
+    pressed: Option<Instant>,
+    released: bool,
+}
+
+impl EStop {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            latch: false,
+            pressed: None,
+            released: false,
+        }
+    }
+
+    /// 是否处于急停状态
+    #[inline]
+    pub fn active(&self) -> bool {
+        self.pressed.is_some()
+    }
+
+    /// 进入急停状态的时刻
+    #[inline]
+    pub fn since(&self) -> Option<Instant> {
+        self.pressed
+    }
+
+    /// 每次查询到急停开关时调用
+    pub fn update(&mut self, time: Instant, power_switch: bool) -> Option<EStopEvent> {
+        match (self.pressed, power_switch) {
+            (None, false) => {
+                self.pressed = Some(time);
+                self.released = false;
+                Some(EStopEvent::Pressed)
+            }
+            (Some(_), false) if self.released => {
+                // 确认前再次按下
+                self.released = false;
+                Some(EStopEvent::Pressed)
+            }
+            (Some(_), true) if !self.released => {
+                self.released = true;
+                if !self.latch {
+                    self.pressed = None;
+                }
+                Some(EStopEvent::Released)
+            }
+            _ => None,
+        }
+    }
+
+    /// 确认急停，开关仍断开时无法确认
+    pub fn acknowledge(&mut self) -> Option<EStopEvent> {
+        if self.pressed.is_some() && self.released {
+            self.pressed = None;
+            Some(EStopEvent::Acknowledged)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn latched() -> (EStop, Instant) {
+        let mut estop = EStop::new();
+        estop.latch = true;
+        (estop, Instant::now())
+    }
+
+    #[test]
+    fn press_release_acknowledge() {
+        let (mut estop, t0) = latched();
+        assert_eq!(estop.update(t0, true), None);
+        assert!(!estop.active());
+        assert_eq!(estop.update(t0, false), Some(EStopEvent::Pressed));
+        assert_eq!(estop.since(), Some(t0));
+        // 开关仍断开时无法确认
+        assert_eq!(estop.acknowledge(), None);
+        assert_eq!(estop.update(t0 + Duration::from_secs(1), false), None);
+        assert_eq!(
+            estop.update(t0 + Duration::from_secs(2), true),
+            Some(EStopEvent::Released)
+        );
+        // 锁存，恢复闭合后仍处于急停状态
+        assert!(estop.active());
+        assert_eq!(estop.update(t0 + Duration::from_secs(3), true), None);
+        assert_eq!(estop.acknowledge(), Some(EStopEvent::Acknowledged));
+        assert!(!estop.active());
+        assert_eq!(estop.acknowledge(), None);
+    }
+
+    #[test]
+    fn press_again_before_acknowledge() {
+        let (mut estop, t0) = latched();
+        estop.update(t0, false);
+        estop.update(t0 + Duration::from_secs(1), true);
+        assert_eq!(
+            estop.update(t0 + Duration::from_secs(2), false),
+            Some(EStopEvent::Pressed)
+        );
+        // 保持第一次按下的时刻
+        assert_eq!(estop.since(), Some(t0));
+        assert_eq!(estop.acknowledge(), None);
+        assert_eq!(
+            estop.update(t0 + Duration::from_secs(3), true),
+            Some(EStopEvent::Released)
+        );
+        assert_eq!(estop.acknowledge(), Some(EStopEvent::Acknowledged));
+    }
+
+    #[test]
+    fn unlatch_while_pressed() {
+        let (mut estop, t0) = latched();
+        estop.update(t0, false);
+        // 关闭锁存时开关仍断开，确认无效
+        estop.latch = false;
+        assert_eq!(estop.acknowledge(), None);
+        assert!(estop.active());
+        // 恢复闭合后直接解除
+        assert_eq!(
+            estop.update(t0 + Duration::from_secs(1), true),
+            Some(EStopEvent::Released)
+        );
+        assert!(!estop.active());
+    }
+
+    #[test]
+    fn release_without_latch() {
+        let mut estop = EStop::new();
+        let t0 = Instant::now();
+        assert_eq!(estop.update(t0, false), Some(EStopEvent::Pressed));
+        assert!(estop.active());
+        assert_eq!(estop.update(t0, true), Some(EStopEvent::Released));
+        assert!(!estop.active());
+        assert_eq!(estop.acknowledge(), None);
+    }
+}
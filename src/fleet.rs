@@ -0,0 +1,108 @@
+use crate::{PM1Event, OPEN_TIMEOUT, PM1};
+use driver::{Driver, DriverPacemaker};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+/// 多底盘监控器的事件，均带有底盘名称
+pub enum FleetEvent<'a> {
+    Connected(&'a str, &'a mut PM1),
+    /// 未找到串口或打开失败，稍后重试
+    ConnectFailed(&'a str),
+    Disconnected(&'a str),
+    Event(&'a str, &'a mut PM1, Option<(Instant, PM1Event)>),
+}
+
+/// 同时监控多台底盘。
+///
+/// 底盘由串口路径识别，每台底盘在独立的线程中连接、接收和重连，互不影响。
+/// 底盘名称同时用于查找校准参数和保存的任务，不符合 `PM1::set_name` 要求的名称只用于标记事件。
+pub struct Fleet {
+    chassis: Vec<(String, String)>,
+}
+
+impl Fleet {
+    /// 由 `(串口路径, 底盘名称)` 列表创建，串口路径与 `PortKey` 的字符串形式一致。
+    ///
+    /// 并非每个串口都连接了底盘，因此需要显式给出映射，而不是使用 `Driver::keys` 的全部串口。
+    #[inline]
+    pub fn new(chassis: Vec<(String, String)>) -> Self {
+        Self { chassis }
+    }
+
+    /// 底盘名称
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chassis.iter().map(|(_, name)| name.as_str())
+    }
+
+    /// 阻塞监控所有底盘，`f` 在各底盘的线程中调用。
+    ///
+    /// `f` 返回 `false` 时停止所有底盘的监控，所有线程退出后返回。
+    pub fn join<F>(&self, f: F)
+    where
+        F: Fn(FleetEvent) -> bool + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let stop = Arc::new(AtomicBool::new(false));
+        let threads = self
+            .chassis
+            .iter()
+            .cloned()
+            .map(|(path, name)| {
+                let f = f.clone();
+                let stop = stop.clone();
+                thread::spawn(move || supervise(&path, &name, &*f, &stop))
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 监控一台底盘直到 `stop` 被置位
+fn supervise<F>(path: &str, name: &str, f: &F, stop: &AtomicBool)
+where
+    F: Fn(FleetEvent) -> bool,
+{
+    let emit = |event: FleetEvent<'_>| {
+        if !f(event) {
+            stop.store(true, Ordering::Relaxed);
+        }
+        !stop.load(Ordering::Relaxed)
+    };
+    while !stop.load(Ordering::Relaxed) {
+        let opened = PM1::keys()
+            .into_iter()
+            .find(|key| key.to_string() == path)
+            .and_then(|key| PM1::open(&key, Some(name.into())));
+        let (mut pacemaker, mut pm1) = match opened {
+            Some(pair) => pair,
+            None => {
+                emit(FleetEvent::ConnectFailed(name));
+                thread::sleep(OPEN_TIMEOUT);
+                continue;
+            }
+        };
+        // 串口关闭后 `send` 返回 `false`，询问线程随之退出
+        thread::spawn(move || {
+            let period = <PM1 as Driver>::Pacemaker::period();
+            while pacemaker.send() {
+                thread::sleep(period);
+            }
+        });
+        if !emit(FleetEvent::Connected(name, &mut pm1)) {
+            break;
+        }
+        pm1.join(|pm1, event| emit(FleetEvent::Event(name, pm1, event)));
+        if !stop.load(Ordering::Relaxed) {
+            emit(FleetEvent::Disconnected(name));
+        }
+    }
+}
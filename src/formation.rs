@@ -0,0 +1,190 @@
+use crate::{
+    autopilot::{Autopilot, Step},
+    kinematics::rudder_for_curvature,
+    path::Path,
+    Pose, PM1,
+};
+use pm1_control_model::Physical;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// 领队轨迹中相邻两点的最小间距（m）
+const SPACING: f32 = 0.05;
+/// 领队轨迹保留的最大长度（m）
+const TRAIL_LENGTH: f32 = 20.0;
+/// 领队超过此时间没有更新时原地等待
+const LEADER_TIMEOUT: Duration = Duration::from_millis(500);
+/// 间距误差到速度的增益（1/s）
+const GAP_GAIN: f32 = 0.8;
+/// 最小前视距离（m）
+const LOOKAHEAD: f32 = 0.5;
+/// 前视时间（s）
+const LOOKAHEAD_TIME: f32 = 1.0;
+
+/// 领队发布的状态
+struct LeaderState {
+    trail: VecDeque<(f32, f32)>,
+    pose: Pose,
+    speed: f32,
+    time: Option<Instant>,
+}
+
+/// 在线程之间共享的领队状态。
+///
+/// 领队底盘通过 `PM1::set_leader` 在每次里程计更新时发布位姿，
+/// 跟随者通过 `Follower` 读取。同一进程中的 `Instant` 构成共同的时间基准。
+#[derive(Clone)]
+pub struct Leader(Arc<Mutex<LeaderState>>);
+
+impl Default for Leader {
+    #[inline]
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(LeaderState {
+            trail: VecDeque::new(),
+            pose: Pose::ZERO,
+            speed: 0.0,
+            time: None,
+        })))
+    }
+}
+
+impl Leader {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 领队最近一次发布的时刻和位姿（领队的里程计坐标系）
+    #[inline]
+    pub fn pose(&self) -> Option<(Instant, Pose)> {
+        let state = self.0.lock().unwrap();
+        state.time.map(|t| (t, state.pose))
+    }
+
+    fn publish(&self, time: Instant, pose: Pose, speed: f32) {
+        let mut state = self.0.lock().unwrap();
+        state.time = Some(time);
+        state.pose = pose;
+        state.speed = speed;
+        let p = (pose.x, pose.y);
+        let far = state
+            .trail
+            .back()
+            .is_none_or(|q| (p.0 - q.0).hypot(p.1 - q.1) >= SPACING);
+        if far {
+            state.trail.push_back(p);
+            let max = (TRAIL_LENGTH / SPACING) as usize;
+            while state.trail.len() > max {
+                state.trail.pop_front();
+            }
+        }
+    }
+}
+
+/// 沿领队走过的轨迹跟随，保持沿轨迹的间距。
+///
+/// 创建任务时两台底盘应前后排成一列、朝向相同，跟随者位于领队后方 `distance` 处，
+/// 以此确定两个里程计坐标系之间的关系。
+/// 领队超时未更新时原地等待，任务不会自行结束，需要调用 `stop` 取消。
+pub struct Follower {
+    leader: Leader,
+    distance: f32,
+    speed: f32,
+    frame: Option<Pose>,
+}
+
+impl Follower {
+    /// 在领队后方 `distance`（m）处跟随，最大速度 `speed`（m/s）
+    #[inline]
+    pub fn new(leader: Leader, distance: f32, speed: f32) -> Self {
+        Self {
+            leader,
+            distance,
+            speed: speed.abs(),
+            frame: None,
+        }
+    }
+
+    /// 指定领队里程计坐标系原点在跟随者里程计坐标系中的位姿，代替按初始队形推算
+    #[inline]
+    pub fn with_frame(mut self, frame: Pose) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+}
+
+impl Autopilot for Follower {
+    fn step(&mut self, time: Instant, pm1: &PM1) -> Step {
+        let pose = pm1.pose();
+        let (trail, leader, speed, updated) = {
+            let state = self.leader.0.lock().unwrap();
+            match state.time {
+                Some(t) => (
+                    state.trail.iter().copied().collect::<Vec<_>>(),
+                    state.pose,
+                    state.speed,
+                    t,
+                ),
+                None => return Step::Drive(Physical::RELEASED),
+            }
+        };
+        if time > updated + LEADER_TIMEOUT {
+            return Step::Drive(Physical::RELEASED);
+        }
+        let frame = *self.frame.get_or_insert_with(|| {
+            let expected = pose.compose(&Pose {
+                x: self.distance,
+                y: 0.0,
+                theta: 0.0,
+            });
+            expected.compose(&leader.inverse())
+        });
+
+        // 领队轨迹变换到跟随者的坐标系
+        let mut points = trail
+            .into_iter()
+            .map(|p| frame.transform(p))
+            .collect::<Vec<_>>();
+        points.push(frame.transform((leader.x, leader.y)));
+        let path = Path::new(points);
+        let (_, s, _) = path.nearest((pose.x, pose.y));
+        let gap = path.length() - s;
+
+        let speed = (speed + GAP_GAIN * (gap - self.distance)).clamp(0.0, self.speed);
+        let lookahead = LOOKAHEAD.max(LOOKAHEAD_TIME * pm1.status().physical.speed.abs());
+        let (x, y) = pose.inverse_transform(path.point_at(s + lookahead));
+        let d2 = x * x + y * y;
+        if d2 == 0.0 {
+            return Step::Drive(Physical::RELEASED);
+        }
+        Step::Drive(Physical {
+            speed,
+            rudder: rudder_for_curvature(&pm1.model, 2.0 * y / d2),
+        })
+    }
+}
+
+impl PM1 {
+    /// 将此底盘作为领队，每次里程计更新时发布位姿；`None` 停止发布
+    #[inline]
+    pub fn set_leader(&mut self, leader: Option<Leader>) {
+        self.leader = leader;
+    }
+
+    /// 跟随领队，不阻塞，见 `Follower`
+    #[inline]
+    pub fn follow(&mut self, leader: Leader, distance: f32, speed: f32) {
+        self.start_autopilot(Follower::new(leader, distance, speed));
+    }
+
+    /// 里程计更新后发布领队状态
+    #[inline]
+    pub(crate) fn publish_leader(&self, time: Instant) {
+        if let Some(leader) = &self.leader {
+            leader.publish(time, self.pose, self.status.physical.speed);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    geometry::{polygon_contains, polygon_distance},
+    predict::scale_down,
+    Pose, CONTROL_PERIOD, PM1,
+};
+use pm1_control_model::Physical;
+use std::time::Duration;
+
+/// 检查目标是否越界时向前推演的时长
+const HORIZON: Duration = Duration::from_millis(1500);
+
+/// 里程计坐标系下的虚拟围栏
+#[derive(Clone, Debug)]
+pub enum Geofence {
+    /// 多边形，顶点按顺序排列
+    Polygon(Vec<(f32, f32)>),
+    /// 圆形区域
+    Circle { center: (f32, f32), radius: f32 },
+}
+
+/// 预测将越出围栏时的处理方式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GeofenceAction {
+    /// 降低速度，直到预测轨迹不越界
+    Clamp,
+    /// 直接停止
+    Stop,
+}
+
+impl Geofence {
+    /// 以里程计原点为圆心的圆形围栏
+    #[inline]
+    pub fn radius(radius: f32) -> Self {
+        Self::Circle {
+            center: (0.0, 0.0),
+            radius,
+        }
+    }
+
+    /// 点到围栏边界的有向距离，在围栏内为正
+    pub fn depth(&self, point: (f32, f32)) -> f32 {
+        match self {
+            Self::Circle { center, radius } => {
+                radius - (point.0 - center.0).hypot(point.1 - center.1)
+            }
+            Self::Polygon(vertices) => {
+                let distance = polygon_distance(vertices, point);
+                if polygon_contains(vertices, point) {
+                    distance
+                } else {
+                    -distance
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, point: (f32, f32)) -> bool {
+        self.depth(point) >= 0.0
+    }
+}
+
+impl PM1 {
+    /// 推演 `target`，找到首个越出围栏的位姿。
+    ///
+    /// 已经在围栏外时，只要推演终点比当前更靠近围栏内部就不视为越界。
+    fn geofence_exit(&self, fence: &Geofence, target: Physical) -> Option<Pose> {
+        let steps = (HORIZON.as_millis() / CONTROL_PERIOD.as_millis()) as usize;
+        let depth = fence.depth((self.pose.x, self.pose.y));
+        if depth < 0.0 {
+            let (end, _) = self
+                .control_predictor()
+                .with_target(target)
+                .nth(steps - 1)
+                .unwrap();
+            if fence.depth((end.x, end.y)) > depth {
+                None
+            } else {
+                Some(self.pose)
+            }
+        } else {
+            self.control_predictor()
+                .with_target(target)
+                .take(steps)
+                .map(|(pose, _)| pose)
+                .find(|pose| !fence.contains((pose.x, pose.y)))
+        }
+    }
+
+    /// 根据围栏修正控制目标，返回修正后的目标和预测的越界位姿
+    pub(crate) fn geofence_limit(&self, target: Physical) -> (Physical, Option<Pose>) {
+        let (fence, action) = match &self.geofence {
+            Some(geofence) => geofence,
+            None => return (target, None),
+        };
+        let exit = match self.geofence_exit(fence, target) {
+            Some(exit) => exit,
+            None => return (target, None),
+        };
+        let target = match action {
+            GeofenceAction::Stop => Physical::RELEASED,
+            GeofenceAction::Clamp => scale_down(target, |t| self.geofence_exit(fence, t).is_none()),
+        };
+        (target, Some(exit))
+    }
+}
@@ -0,0 +1,130 @@
+/// 点到线段的距离
+pub(crate) fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let k = if len2 > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.0 - a.0 - k * dx).hypot(p.1 - a.1 - k * dy)
+}
+
+/// 点到多边形边界的最短距离
+pub(crate) fn polygon_distance(vertices: &[(f32, f32)], p: (f32, f32)) -> f32 {
+    (0..vertices.len())
+        .map(|i| segment_distance(p, vertices[i], vertices[(i + 1) % vertices.len()]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// 射线法判断点是否在多边形内
+pub(crate) fn polygon_contains(vertices: &[(f32, f32)], p: (f32, f32)) -> bool {
+    let mut inside = false;
+    for (i, a) in vertices.iter().enumerate() {
+        let b = vertices[(i + 1) % vertices.len()];
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// 判断两条线段是否相交
+pub(crate) fn segments_intersect(
+    a: (f32, f32),
+    b: (f32, f32),
+    c: (f32, f32),
+    d: (f32, f32),
+) -> bool {
+    #[inline]
+    fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    }
+
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    (d1 * d2 < 0.0 && d3 * d4 < 0.0)
+        || (d1 == 0.0 && segment_distance(a, c, d) == 0.0)
+        || (d2 == 0.0 && segment_distance(b, c, d) == 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [(f32, f32); 4] = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+    /// 凹多边形，缺口位于 (1, 1) 到 (1, 2) 之间
+    const NOTCH: [(f32, f32); 6] = [
+        (0.0, 0.0),
+        (2.0, 0.0),
+        (2.0, 2.0),
+        (1.5, 2.0),
+        (1.0, 1.0),
+        (0.0, 2.0),
+    ];
+
+    #[test]
+    fn contains_convex() {
+        assert!(polygon_contains(&SQUARE, (1.0, 1.0)));
+        assert!(polygon_contains(&SQUARE, (0.1, 1.9)));
+        assert!(!polygon_contains(&SQUARE, (-0.1, 1.0)));
+        assert!(!polygon_contains(&SQUARE, (1.0, 2.1)));
+        assert!(!polygon_contains(&SQUARE, (3.0, 3.0)));
+    }
+
+    #[test]
+    fn contains_concave() {
+        assert!(polygon_contains(&NOTCH, (1.0, 0.5)));
+        assert!(polygon_contains(&NOTCH, (1.8, 1.8)));
+        assert!(!polygon_contains(&NOTCH, (1.0, 1.5)));
+    }
+
+    #[test]
+    fn contains_degenerate() {
+        assert!(!polygon_contains(&[], (0.0, 0.0)));
+        assert!(!polygon_contains(&[(0.0, 0.0), (1.0, 1.0)], (0.5, 0.5)));
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(segment_distance((1.0, 1.0), (0.0, 0.0), (2.0, 0.0)), 1.0);
+        assert_eq!(segment_distance((3.0, 0.0), (0.0, 0.0), (2.0, 0.0)), 1.0);
+        assert_eq!(segment_distance((3.0, 4.0), (0.0, 0.0), (0.0, 0.0)), 5.0);
+        assert_eq!(polygon_distance(&SQUARE, (1.0, 0.5)), 0.5);
+        assert_eq!(polygon_distance(&SQUARE, (1.0, 3.0)), 1.0);
+    }
+
+    #[test]
+    fn intersect() {
+        // 交叉
+        assert!(segments_intersect(
+            (0.0, 0.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+            (2.0, 0.0)
+        ));
+        // 平行
+        assert!(!segments_intersect(
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (0.0, 1.0),
+            (2.0, 1.0)
+        ));
+        // 延长线相交，线段不相交
+        assert!(!segments_intersect(
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, -1.0),
+            (2.0, 1.0)
+        ));
+        // 端点落在另一条线段上
+        assert!(segments_intersect(
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 0.0),
+            (2.0, 0.0)
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{node::*, CONTROL_PERIOD};
+use std::time::Instant;
+
+/// 被主动询问的节点
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Node {
+    /// 转向控制器
+    Tcu,
+    /// 左轮动力控制器
+    EcuLeft,
+    /// 右轮动力控制器
+    EcuRight,
+    /// 车辆控制器
+    Vcu,
+}
+
+impl Node {
+    const ALL: [Node; 4] = [Node::Tcu, Node::EcuLeft, Node::EcuRight, Node::Vcu];
+
+    /// 与 `PM1Pacemaker` 的询问计划一致的询问间隔（控制周期数）
+    #[inline]
+    const fn interval(self) -> u32 {
+        match self {
+            Node::Tcu => 1,
+            Node::EcuLeft | Node::EcuRight => 2,
+            Node::Vcu => 10,
+        }
+    }
+
+    /// 判断一条带数据的消息是否是某个节点对询问的响应
+    fn responding(node_type: u8, node_index: u8, msg_type: u8) -> Option<Self> {
+        match (node_type, node_index, msg_type) {
+            (tcu::TYPE, _, tcu::CURRENT_POSITION) => Some(Node::Tcu),
+            (ecu::TYPE, 0, ecu::CURRENT_POSITION) => Some(Node::EcuLeft),
+            (ecu::TYPE, 1, ecu::CURRENT_POSITION) => Some(Node::EcuRight),
+            (vcu::TYPE, _, vcu::POWER_SWITCH | vcu::BATTERY_PERCENT) => Some(Node::Vcu),
+            _ => None,
+        }
+    }
+}
+
+/// 记录每个节点最后一次响应的时间，连续错过 `misses` 次询问即认为节点失联
+pub(crate) struct Health {
+    pub misses: u32,
+    last: [Instant; 4],
+    alive: [bool; 4],
+}
+
+impl Health {
+    #[inline]
+    pub fn new(now: Instant) -> Self {
+        Self {
+            misses: 3,
+            last: [now; 4],
+            alive: [true; 4],
+        }
+    }
+
+    #[inline]
+    pub fn alive(&self, node: Node) -> bool {
+        self.alive[node as usize]
+    }
+
+    /// 记录一条消息，返回恢复响应的节点
+    pub fn receive(
+        &mut self,
+        time: Instant,
+        node_type: u8,
+        node_index: u8,
+        msg_type: u8,
+    ) -> Option<Node> {
+        let node = Node::responding(node_type, node_index, msg_type)?;
+        let i = node as usize;
+        self.last[i] = time;
+        if self.alive[i] {
+            None
+        } else {
+            self.alive[i] = true;
+            Some(node)
+        }
+    }
+
+    /// 检查超时，返回新失联的节点
+    pub fn check(&mut self, time: Instant) -> impl Iterator<Item = Node> + '_ {
+        let misses = self.misses;
+        Node::ALL.into_iter().filter(move |node| {
+            let i = *node as usize;
+            // 多等待半个周期，容忍询问和响应的时间抖动
+            let timeout = CONTROL_PERIOD * (node.interval() * misses) + CONTROL_PERIOD / 2;
+            if self.alive[i] && time > self.last[i] + timeout {
+                self.alive[i] = false;
+                true
+            } else {
+                false
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn lost(health: &mut Health, time: Instant) -> Vec<Node> {
+        health.check(time).collect()
+    }
+
+    #[test]
+    fn timeout_by_interval() {
+        let t0 = Instant::now();
+        let mut health = Health::new(t0);
+        let ms = |ms| t0 + Duration::from_millis(ms);
+        // 转向控制器每周期询问一次，连续错过 3 次再多等半个周期
+        assert!(lost(&mut health, ms(140)).is_empty());
+        assert_eq!(lost(&mut health, ms(141)), [Node::Tcu]);
+        // 已失联的节点不重复报告
+        assert!(lost(&mut health, ms(200)).is_empty());
+        assert_eq!(lost(&mut health, ms(261)), [Node::EcuLeft, Node::EcuRight]);
+        assert!(lost(&mut health, ms(1220)).is_empty());
+        assert_eq!(lost(&mut health, ms(1221)), [Node::Vcu]);
+        assert!(!health.alive(Node::Tcu));
+        assert!(!health.alive(Node::Vcu));
+    }
+
+    #[test]
+    fn response_restores_node() {
+        let t0 = Instant::now();
+        let mut health = Health::new(t0);
+        let ms = |ms| t0 + Duration::from_millis(ms);
+        // 仍在线的节点响应时不报告恢复
+        assert_eq!(
+            health.receive(ms(100), tcu::TYPE, 0, tcu::CURRENT_POSITION),
+            None
+        );
+        assert!(lost(&mut health, ms(240)).is_empty());
+        assert_eq!(lost(&mut health, ms(241)), [Node::Tcu]);
+        assert_eq!(
+            health.receive(ms(250), tcu::TYPE, 0, tcu::CURRENT_POSITION),
+            Some(Node::Tcu)
+        );
+        assert!(health.alive(Node::Tcu));
+        // 与询问无关的消息不影响在线状态
+        assert_eq!(health.receive(ms(260), ecu::TYPE, 0, STATE), None);
+    }
+
+    #[test]
+    fn misses_extend_timeout() {
+        let t0 = Instant::now();
+        let mut health = Health::new(t0);
+        health.misses = 5;
+        assert!(lost(&mut health, t0 + Duration::from_millis(220)).is_empty());
+        assert_eq!(
+            lost(&mut health, t0 + Duration::from_millis(221)),
+            [Node::Tcu]
+        );
+    }
+}
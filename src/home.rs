@@ -0,0 +1,80 @@
+use crate::{Mpc, PurePursuit, PM1};
+
+/// 相邻两个足迹点的最小间距（m）
+const SPACING: f32 = 0.2;
+/// 新的足迹点距离较早的足迹点小于此值（m）时视为回到原处，删除中间的环路
+const LOOP_DISTANCE: f32 = 0.1;
+/// 足迹点数量上限，超过时隔点抽稀
+const MAX_BREADCRUMBS: usize = 10000;
+
+/// 返回起点的方式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReturnMode {
+    /// 沿足迹原路返回，只经过走过的地方
+    Retrace,
+    /// 使用模型预测控制器直接驶向起点，绕开已知的障碍物
+    Direct,
+}
+
+impl PM1 {
+    /// 上电或上次 `reset_pose` 以来的足迹，位于里程计坐标系，第一个点为起点
+    #[inline]
+    pub fn breadcrumbs(&self) -> &[(f32, f32)] {
+        &self.breadcrumbs
+    }
+
+    /// 以最大速度 `speed` 返回起点，不阻塞，到达时产生 `PM1Event::AutopilotFinished` 事件。
+    ///
+    /// `ReturnMode::Retrace` 的足迹通常在身后，底盘先原地转向足迹再前进。
+    pub fn return_home(&mut self, mode: ReturnMode, speed: f32) {
+        let home = self.breadcrumbs[0];
+        match mode {
+            ReturnMode::Retrace => {
+                let path = std::iter::once((self.pose.x, self.pose.y))
+                    .chain(self.breadcrumbs.iter().rev().copied())
+                    .collect();
+                self.start_autopilot(PurePursuit::new(path, speed));
+            }
+            ReturnMode::Direct => {
+                self.start_autopilot(Mpc::new(vec![home], speed));
+            }
+        }
+    }
+
+    /// 从当前位置重新开始记录足迹
+    #[inline]
+    pub(crate) fn reset_breadcrumbs(&mut self) {
+        self.breadcrumbs.clear();
+        self.breadcrumbs.push((self.pose.x, self.pose.y));
+    }
+
+    /// 里程计更新后记录足迹
+    pub(crate) fn drop_breadcrumb(&mut self) {
+        let p = (self.pose.x, self.pose.y);
+        let distance = |q: &(f32, f32)| (p.0 - q.0).hypot(p.1 - q.1);
+        if self
+            .breadcrumbs
+            .last()
+            .is_some_and(|q| distance(q) < SPACING)
+        {
+            return;
+        }
+        // 回到走过的地方时删除环路，返回时不必再绕一圈
+        let n = self.breadcrumbs.len().saturating_sub(1);
+        if let Some(i) = self.breadcrumbs[..n]
+            .iter()
+            .position(|q| distance(q) < LOOP_DISTANCE)
+        {
+            self.breadcrumbs.truncate(i + 1);
+            return;
+        }
+        self.breadcrumbs.push(p);
+        if self.breadcrumbs.len() > MAX_BREADCRUMBS {
+            let mut i = 0;
+            self.breadcrumbs.retain(|_| {
+                i += 1;
+                i % 2 == 1
+            });
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{Dynamics, TraceRecord, CONTROL_PERIOD};
+use pm1_control_model::{Optimizer, Physical, Pm1Model};
+use std::fmt::Display;
+
+/// 后轮误差大于此值（rad）的周期用于估计转向角速度
+const RUDDER_SATURATION: f32 = 0.1;
+
+/// 参数辨识的结果
+#[derive(Clone, Copy, Debug)]
+pub struct Identification {
+    pub dynamics: Dynamics,
+    /// 单步速度预测的均方根误差（m/s）
+    pub speed_rmse: f32,
+    /// 单步速度预测的决定系数
+    pub speed_r2: f32,
+    /// 单步后轮角度预测的均方根误差（rad）
+    pub rudder_rmse: f32,
+    /// 参与拟合的控制周期数
+    pub samples: usize,
+}
+
+/// 相邻两个控制周期构成的样本
+struct Sample {
+    target: Physical,
+    /// 经过限制后发出的目标，后轮追踪的是它而不是请求的目标
+    sent: Physical,
+    current: Physical,
+    next: Physical,
+    dt: f32,
+}
+
+/// 将记录整理成样本，实际速度由最近一次里程计增量换算
+fn samples(records: &[TraceRecord], model: &Pm1Model) -> Vec<Sample> {
+    let mut speed = 0.0;
+    let mut last_wheels = None;
+    let mut controls = Vec::new();
+    for record in records {
+        match *record {
+            TraceRecord::Wheels { time, wheels } => {
+                if let Some(last) = last_wheels.replace(time) {
+                    if time > last {
+                        speed = model.wheels_to_velocity(wheels).v / (time - last);
+                    }
+                }
+            }
+            TraceRecord::Control {
+                time,
+                target,
+                sent,
+                rudder,
+            } => controls.push((time, target, sent, Physical { speed, rudder })),
+        }
+    }
+    controls
+        .windows(2)
+        .filter(|w| w[1].0 > w[0].0)
+        .map(|w| Sample {
+            target: w[0].1,
+            sent: w[0].2,
+            current: w[0].3,
+            next: w[1].3,
+            dt: w[1].0 - w[0].0,
+        })
+        .collect()
+}
+
+/// 使用给定参数时单步速度预测的误差平方和
+fn speed_error(optimizer: (f32, f32), samples: &[Sample]) -> f32 {
+    let mut optimizer = Optimizer::new(optimizer.0, optimizer.1, CONTROL_PERIOD);
+    samples
+        .iter()
+        .map(|s| {
+            let e = optimizer.optimize_speed(s.target, s.current) - s.next.speed;
+            e * e
+        })
+        .sum()
+}
+
+/// 在网格上搜索误差最小的优化器参数，再在最优点附近细化一次
+fn fit_optimizer(samples: &[Sample]) -> (f32, f32) {
+    let search = |a0: f32, a1: f32, b0: f32, b1: f32, n: usize| {
+        let mut best = (f32::INFINITY, (a0, b0));
+        for i in 0..=n {
+            for j in 0..=n {
+                let params = (
+                    a0 + (a1 - a0) * i as f32 / n as f32,
+                    b0 + (b1 - b0) * j as f32 / n as f32,
+                );
+                let error = speed_error(params, samples);
+                if error < best.0 {
+                    best = (error, params);
+                }
+            }
+        }
+        best.1
+    };
+    let (a, b) = search(0.05, 2.0, 0.1, 3.0, 40);
+    let (da, db) = (1.95 / 40.0, 2.9 / 40.0);
+    search((a - da).max(0.01), a + da, (b - db).max(0.01), b + db, 20)
+}
+
+/// 取后轮误差较大（转向电机饱和）的周期中转向角速度的中位数，不是正数时返回 `None`
+fn fit_rudder_rate(samples: &[Sample]) -> Option<f32> {
+    let mut rates = samples
+        .iter()
+        .filter(|s| (s.sent.rudder - s.current.rudder).abs() > RUDDER_SATURATION)
+        .map(|s| (s.next.rudder - s.current.rudder).abs() / s.dt)
+        .collect::<Vec<_>>();
+    if rates.is_empty() {
+        return None;
+    }
+    rates.sort_by(f32::total_cmp);
+    // 后轮卡住时中位数为零，不能作为转向角速度
+    Some(rates[rates.len() / 2]).filter(|rate| *rate > 0.0)
+}
+
+/// 从控制过程记录中辨识优化器参数和后轮转向角速度。
+///
+/// 样本不足时返回 `None`；记录中没有足够的转向动作时沿用默认的转向角速度。
+pub fn identify(records: &[TraceRecord], model: &Pm1Model) -> Option<Identification> {
+    let samples = samples(records, model);
+    if samples.len() < 2 {
+        return None;
+    }
+    let optimizer = fit_optimizer(&samples);
+    let rudder_rate = fit_rudder_rate(&samples).unwrap_or(Dynamics::default().rudder_rate);
+
+    let n = samples.len() as f32;
+    let sse = speed_error(optimizer, &samples);
+    let mean = samples.iter().map(|s| s.next.speed).sum::<f32>() / n;
+    let sst = samples
+        .iter()
+        .map(|s| (s.next.speed - mean).powi(2))
+        .sum::<f32>();
+    let rudder_sse = samples
+        .iter()
+        .map(|s| {
+            let step = rudder_rate * s.dt;
+            let predicted =
+                s.current.rudder + (s.sent.rudder - s.current.rudder).clamp(-step, step);
+            (predicted - s.next.rudder).powi(2)
+        })
+        .sum::<f32>();
+    Some(Identification {
+        dynamics: Dynamics {
+            optimizer,
+            rudder_rate,
+        },
+        speed_rmse: (sse / n).sqrt(),
+        speed_r2: if sst > 0.0 { 1.0 - sse / sst } else { 0.0 },
+        rudder_rmse: (rudder_sse / n).sqrt(),
+        samples: samples.len(),
+    })
+}
+
+impl Display for Identification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Optimizer: ({}, {}) | Rudder rate: {}rad/s | Speed RMSE: {}m/s (R² = {}) | Rudder RMSE: {}rad | Samples: {}",
+            self.dynamics.optimizer.0,
+            self.dynamics.optimizer.1,
+            self.dynamics.rudder_rate,
+            self.speed_rmse,
+            self.speed_r2,
+            self.rudder_rmse,
+            self.samples,
+        )
+    }
+}
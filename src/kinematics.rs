@@ -0,0 +1,33 @@
+use pm1_control_model::{Physical, Pm1Model, Velocity, Wheels};
+use std::f32::consts::FRAC_PI_2;
+
+/// 单位速度下，后轮处于 `rudder` 时两个驱动轮的转速
+#[inline]
+pub(crate) fn unit_wheels(model: &Pm1Model, rudder: f32) -> Wheels {
+    model.physical_to_wheels(Physical { speed: 1.0, rudder })
+}
+
+/// 单位速度下，后轮处于 `rudder` 时底盘的线速度和角速度。
+///
+/// 速度与两者成正比，因此任意速度下的运动都可以由此缩放得到。
+#[inline]
+pub(crate) fn unit_velocity(model: &Pm1Model, rudder: f32) -> Velocity {
+    model.wheels_to_velocity(unit_wheels(model, rudder))
+}
+
+/// 前进时使底盘运动曲率（1/m，左转为正）等于 `curvature` 的后轮角度
+pub(crate) fn rudder_for_curvature(model: &Pm1Model, curvature: f32) -> f32 {
+    // 单位速度下 w - k·v 随后轮角度单调变化，二分查找其零点
+    let increasing = unit_velocity(model, 0.1).w > unit_velocity(model, -0.1).w;
+    let (mut lo, mut hi) = (-FRAC_PI_2, FRAC_PI_2);
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        let u = unit_velocity(model, mid);
+        if (u.w - curvature * u.v < 0.0) == increasing {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
@@ -3,19 +3,85 @@ use model::{Pm1Model, Pm1Predictor, TrajectoryPredictor};
 use pm1_control_model::{Motor, Optimizer, Physical, Wheels};
 use serial_port::{Port, PortKey, SerialPort};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     f32::consts::FRAC_PI_2,
     fmt::Display,
     sync::{Arc, Mutex, Weak},
     time::{Duration, Instant},
 };
 
+mod assist;
 mod autocan;
+mod autopilot;
+mod battery;
+mod calibration;
+mod diagnosis;
 mod differential;
+mod estop;
+mod fleet;
+mod formation;
+mod geofence;
+mod geometry;
+mod health;
+mod home;
+mod identify;
+mod kinematics;
+mod limits;
+mod mission;
+mod motion;
+mod mpc;
+mod obstacle;
+mod path;
+mod pose;
+mod predict;
+mod profile;
+mod pursuit;
+mod schedule;
+mod stop;
+mod teach;
+mod trace;
+mod validation;
 
 use self::node::*;
+use assist::{Cruise, HeadingHold};
 use autocan::{Message, MessageBuffer};
+use battery::BatteryMonitor;
+use calibration::RudderCalibration;
+use diagnosis::{RudderMonitor, WheelMonitor};
 use differential::Differential;
+use estop::EStop;
+use health::Health;
+use limits::Limiter;
+use mission::Mission;
+use pose::Odometer;
+use trace::Tracer;
+use validation::Validator;
+
+pub use autopilot::{Autopilot, AutopilotResult, Step};
+pub use battery::{BatteryLevel, BatteryPolicy};
+pub use calibration::{Calibration, CalibrationStore};
+pub use diagnosis::{Diagnostic, Fault, Severity};
+pub use estop::EStopEvent;
+pub use fleet::{Fleet, FleetEvent};
+pub use formation::{Follower, Leader};
+pub use geofence::{Geofence, GeofenceAction};
+pub use health::Node;
+pub use home::ReturnMode;
+pub use identify::{identify, Identification};
+pub use limits::Limits;
+pub use mission::{MissionEvent, MissionStatus, MissionStore, Waypoint};
+pub use motion::Motion;
+pub use mpc::{Mpc, MpcWeights};
+pub use obstacle::{Footprint, Obstacle};
+pub use pose::Pose;
+pub use predict::{ControlPredictor, Dynamics};
+pub use profile::Profile;
+pub use pursuit::PurePursuit;
+pub use schedule::{chirp, Schedule, Waveform};
+pub use stop::{StopMode, StopPolicy, StopReason};
+pub use teach::{Route, RoutePoint};
+pub use trace::{read_trace, TraceRecord};
+pub use validation::{validate, ErrorStats, ValidationReport};
 
 pub extern crate driver;
 pub extern crate pm1_control_model as model;
@@ -35,6 +101,7 @@ const OPEN_TIMEOUT: Duration = Duration::from_secs(1);
 /// - 缓存并随时读取底盘状态
 /// - 控制底盘移动
 pub struct PM1 {
+    name: Option<String>,
     port: Arc<Port>,
     buffer: MessageBuffer<32>,
     last_time: Instant,
@@ -43,10 +110,49 @@ pub struct PM1 {
     state_memory: HashMap<(u8, u8), u8>,
     status: PM1Status,
     target: (Instant, Physical),
+    events: VecDeque<(Instant, PM1Event)>,
+
+    stop_policy: StopPolicy,
+    stop_requested: bool,
+    stopping: Option<StopReason>,
+    hold: Option<(f32, f32)>,
+    wheels_position: (f32, f32),
+    last_motion: Instant,
+    odometer: Odometer,
+    pose: Pose,
+
+    geofence: Option<(Geofence, GeofenceAction)>,
+    geofence_violated: bool,
+    poses: VecDeque<(Instant, Pose)>,
+    obstacles: Option<(Instant, Vec<Obstacle>)>,
+    footprint: Footprint,
+    collision_predicted: bool,
+    battery: BatteryMonitor,
+    estop: EStop,
+    health: Health,
+    wheel_monitor: WheelMonitor,
+    rudder_monitor: RudderMonitor,
+    stop_on_rudder_fault: bool,
+    rudder_trim: f32,
+    rudder_calibration: Option<RudderCalibration>,
+    model_calibration: Option<(f32, f32)>,
+    dynamics: Dynamics,
+    tracer: Option<Tracer>,
+    profiles: HashMap<String, Profile>,
+    profile: Option<String>,
+    validator: Option<(Instant, Validator)>,
+    autopilot: Option<Box<dyn Autopilot>>,
+    mission: Option<Mission>,
+    recording: Option<Route>,
+    breadcrumbs: Vec<(f32, f32)>,
+    heading_hold: Option<HeadingHold>,
+    cruise: Cruise,
+    leader: Option<Leader>,
 
     pub model: Pm1Model,
     differential: Differential,
     optimizer: Optimizer,
+    limiter: Limiter,
 }
 
 #[derive(Clone, Copy)]
@@ -72,6 +178,28 @@ pub enum PM1Event {
     PowerSwitch(bool),
     Physical(Physical),
     Wheels(Wheels),
+    /// 预测控制目标将越出围栏，参数为预测的越界位姿
+    GeofenceViolation(Pose),
+    /// 预测控制目标将与障碍物碰撞，参数为预测的碰撞前时长
+    CollisionPredicted(Duration),
+    /// 电量等级发生变化
+    BatteryLevel(BatteryLevel),
+    /// 急停状态变化
+    EStop(EStopEvent),
+    /// 节点连续多次未响应询问
+    NodeLost(Node),
+    /// 失联的节点恢复响应
+    NodeRecovered(Node),
+    /// 故障诊断结果
+    Diagnostic(Diagnostic),
+    /// 后轮零偏校准完成，参数为新的零偏
+    RudderCalibrated(f32),
+    /// 自动驾驶任务的进度
+    AutopilotProgress(f32),
+    /// 自动驾驶任务结束
+    AutopilotFinished(AutopilotResult),
+    /// 航点任务的进展
+    Mission(MissionEvent),
 }
 
 impl DriverPacemaker for PM1Pacemaker {
@@ -112,6 +240,7 @@ impl PM1 {
     #[inline]
     pub fn set_target(&mut self, target: (Instant, Physical)) {
         self.target = (target.0 + TARGET_MEMORY_TIMEOUT, target.1);
+        self.stop_requested = false;
     }
 
     #[inline]
@@ -119,6 +248,159 @@ impl PM1 {
         self.set_target((Instant::now(), target))
     }
 
+    /// 立即按照停止策略中 `command` 指定的方式停止，同时取消正在执行的自动驾驶任务和航点任务。
+    ///
+    /// 再次调用 `set_target` 或 `drive` 即可恢复控制。
+    #[inline]
+    pub fn stop(&mut self) {
+        let now = Instant::now();
+        self.halt(now);
+        self.abort_autopilot(now);
+        self.abort_mission(now);
+    }
+
+    /// 按照 `command` 停止策略停止，不影响正在执行的任务
+    #[inline]
+    pub(crate) fn halt(&mut self, time: Instant) {
+        self.target = (time, Physical::RELEASED);
+        self.stop_requested = true;
+    }
+
+    #[inline]
+    pub fn stop_policy(&self) -> StopPolicy {
+        self.stop_policy
+    }
+
+    #[inline]
+    pub fn set_stop_policy(&mut self, policy: StopPolicy) {
+        self.stop_policy = policy;
+    }
+
+    /// 当前正在执行的停止及其原因
+    #[inline]
+    pub fn stopping(&self) -> Option<(StopReason, StopMode)> {
+        self.stopping
+            .map(|reason| (reason, self.stop_policy.mode(reason)))
+    }
+
+    /// 里程计坐标系下的当前位姿
+    #[inline]
+    pub fn pose(&self) -> Pose {
+        self.pose
+    }
+
+    /// 重置里程计坐标系下的当前位姿，同时从此处重新记录足迹
+    #[inline]
+    pub fn reset_pose(&mut self, pose: Pose) {
+        self.odometer = Odometer::new(pose);
+        self.pose = pose;
+        self.reset_breadcrumbs();
+    }
+
+    /// 设置虚拟围栏，围栏位于里程计坐标系
+    #[inline]
+    pub fn set_geofence(&mut self, fence: Geofence, action: GeofenceAction) {
+        self.geofence = Some((fence, action));
+    }
+
+    #[inline]
+    pub fn clear_geofence(&mut self) {
+        self.geofence = None;
+        self.geofence_violated = false;
+    }
+
+    /// 开启或关闭急停锁存。
+    ///
+    /// 开启后，急停开关恢复闭合也不会继续运动，必须调用 `acknowledge_estop` 确认。
+    #[inline]
+    pub fn set_estop_latch(&mut self, latch: bool) {
+        self.estop.latch = latch;
+        if !latch {
+            self.acknowledge_estop();
+        }
+    }
+
+    /// 确认急停以恢复控制，急停开关仍断开时返回 `false`
+    pub fn acknowledge_estop(&mut self) -> bool {
+        if let Some(e) = self.estop.acknowledge() {
+            let now = Instant::now();
+            self.target = (now, Physical::RELEASED);
+            self.events.push_back((now, PM1Event::EStop(e)));
+            true
+        } else {
+            !self.estop.active()
+        }
+    }
+
+    /// 是否处于急停状态，包括急停开关恢复后尚未确认的锁存状态
+    #[inline]
+    pub fn estop_active(&self) -> bool {
+        self.estop.active()
+    }
+
+    /// 底盘因急停而停止的时长
+    #[inline]
+    pub fn estop_duration(&self) -> Option<Duration> {
+        self.estop.since().map(|t| t.elapsed())
+    }
+
+    /// 节点是否在按时响应询问
+    #[inline]
+    pub fn node_alive(&self, node: Node) -> bool {
+        self.health.alive(node)
+    }
+
+    /// 设置连续错过多少次询问后认为节点失联
+    #[inline]
+    pub fn set_node_timeout(&mut self, misses: u32) {
+        self.health.misses = misses.max(1);
+    }
+
+    /// 诊断出后轮故障时是否自动停止，停止方式由 `StopPolicy::rudder_fault` 决定。
+    ///
+    /// 停止后故障一直保持，调用 `acknowledge_rudder_fault` 确认后才能恢复控制。
+    #[inline]
+    pub fn set_stop_on_rudder_fault(&mut self, stop: bool) {
+        self.stop_on_rudder_fault = stop;
+    }
+
+    #[inline]
+    pub fn battery_policy(&self) -> BatteryPolicy {
+        self.battery.policy
+    }
+
+    #[inline]
+    pub fn set_battery_policy(&mut self, policy: BatteryPolicy) {
+        self.battery.policy = policy;
+        if let Some(level) = self.battery.refresh() {
+            self.events
+                .push_back((Instant::now(), PM1Event::BatteryLevel(level)));
+        }
+    }
+
+    #[inline]
+    pub fn battery_level(&self) -> BatteryLevel {
+        self.battery.level()
+    }
+
+    /// 根据观测到的放电速率和近期负载估计剩余运行时间
+    #[inline]
+    pub fn remaining_runtime(&self) -> Option<Duration> {
+        self.battery.remaining()
+    }
+
+    #[inline]
+    pub fn limits(&self) -> Limits {
+        self.limiter.limits()
+    }
+
+    /// 设置运行时限制，从下一个控制周期开始生效，负数按绝对值处理，NaN 视为不限制
+    #[inline]
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limiter.set_limits(limits);
+        self.profile = None;
+    }
+
     #[inline]
     pub fn status_predictor(&self) -> Pm1Predictor {
         Pm1Predictor::new(self.optimizer, CONTROL_PERIOD)
@@ -134,6 +416,89 @@ impl PM1 {
     }
 }
 
+impl PM1 {
+    /// 打开串口，给出合法的底盘名称时加载该底盘的校准参数和保存的任务，
+    /// 未给出时使用 `PM1::default_name`
+    pub(crate) fn open(key: &PortKey, name: Option<String>) -> Option<(PM1Pacemaker, Self)> {
+        match Port::open(key, 115200, MESSAGE_RECEIVE_TIMEOUT.as_millis() as u32) {
+            Ok(port) => {
+                let now = Instant::now();
+                let port = Arc::new(port);
+                let sender = PM1Pacemaker {
+                    port: Arc::downgrade(&port),
+
+                    next: now,
+                    index: 0,
+                };
+                sender.send_len(5);
+                let mut pm1 = PM1 {
+                    name: None,
+                    port,
+                    buffer: Default::default(),
+                    last_time: now,
+
+                    using_pad: now,
+                    state_memory: HashMap::new(),
+                    status: PM1Status {
+                        battery_percent: 0,
+                        power_switch: false,
+                        physical: Physical::RELEASED,
+                    },
+                    target: (now, Physical::RELEASED),
+                    events: VecDeque::new(),
+
+                    stop_policy: Default::default(),
+                    stop_requested: false,
+                    stopping: None,
+                    hold: None,
+                    wheels_position: (0.0, 0.0),
+                    last_motion: now,
+                    odometer: Odometer::new(Pose::ZERO),
+                    pose: Pose::ZERO,
+
+                    geofence: None,
+                    geofence_violated: false,
+                    poses: VecDeque::new(),
+                    obstacles: None,
+                    footprint: Default::default(),
+                    collision_predicted: false,
+                    battery: BatteryMonitor::new(),
+                    estop: EStop::new(),
+                    health: Health::new(now),
+                    wheel_monitor: Default::default(),
+                    rudder_monitor: RudderMonitor::new(now),
+                    stop_on_rudder_fault: false,
+                    rudder_trim: 0.0,
+                    rudder_calibration: None,
+                    model_calibration: None,
+                    dynamics: Default::default(),
+                    tracer: None,
+                    profiles: HashMap::new(),
+                    profile: None,
+                    validator: None,
+                    autopilot: None,
+                    mission: None,
+                    recording: None,
+                    breadcrumbs: vec![(0.0, 0.0)],
+                    heading_hold: None,
+                    cruise: Default::default(),
+                    leader: None,
+
+                    differential: Differential::new(),
+                    model: Default::default(),
+                    optimizer: Dynamics::default().optimizer(),
+                    limiter: Limiter::new(CONTROL_PERIOD),
+                };
+                if let Some(name) = name.or_else(PM1::default_name) {
+                    pm1.set_name(&name);
+                }
+                Some((sender, pm1))
+            }
+            Err(_) => None,
+        }
+    }
+}
+
 impl Driver for PM1 {
     type Key = PortKey;
     type Pacemaker = PM1Pacemaker;
@@ -149,42 +514,9 @@ impl Driver for PM1 {
         OPEN_TIMEOUT
     }
 
+    #[inline]
     fn new(key: &Self::Key) -> Option<(Self::Pacemaker, Self)> {
-        match Port::open(key, 115200, MESSAGE_RECEIVE_TIMEOUT.as_millis() as u32) {
-            Ok(port) => {
-                let now = Instant::now();
-                let port = Arc::new(port);
-                let sender = PM1Pacemaker {
-                    port: Arc::downgrade(&port),
-
-                    next: now,
-                    index: 0,
-                };
-                sender.send_len(5);
-                Some((
-                    sender,
-                    PM1 {
-                        port,
-                        buffer: Default::default(),
-                        last_time: now,
-
-                        using_pad: now,
-                        state_memory: HashMap::new(),
-                        status: PM1Status {
-                            battery_percent: 0,
-                            power_switch: false,
-                            physical: Physical::RELEASED,
-                        },
-                        target: (now, Physical::RELEASED),
-
-                        differential: Differential::new(),
-                        model: Default::default(),
-                        optimizer: Optimizer::new(0.5, 1.2, CONTROL_PERIOD),
-                    },
-                ))
-            }
-            Err(_) => None,
-        }
+        Self::open(key, None)
     }
 
     fn join<F>(&mut self, mut f: F) -> bool
@@ -201,6 +533,12 @@ impl Driver for PM1 {
                     // 如果回调指示不要继续阻塞，立即退出
                     return true;
                 }
+                // 控制过程中产生的其他事件
+                while let Some(event) = self.events.pop_front() {
+                    if !f(self, Some(event)) {
+                        return true;
+                    }
+                }
             } else if self.last_time > time + MESSAGE_PARSE_TIMEOUT {
                 // 解析超时
                 return false;
@@ -274,7 +612,10 @@ impl PM1 {
     }
 
     #[inline]
-    fn update_battery_percent(&mut self, battery_percent: u8) -> Option<PM1Event> {
+    fn update_battery_percent(&mut self, time: Instant, battery_percent: u8) -> Option<PM1Event> {
+        if let Some(level) = self.battery.update(time, battery_percent) {
+            self.events.push_back((time, PM1Event::BatteryLevel(level)));
+        }
         if battery_percent != self.status.battery_percent {
             self.status.battery_percent = battery_percent;
             Some(PM1Event::Battery(battery_percent))
@@ -284,8 +625,11 @@ impl PM1 {
     }
 
     #[inline]
-    fn update_power_switch(&mut self, power_switch: u8) -> Option<PM1Event> {
+    fn update_power_switch(&mut self, time: Instant, power_switch: u8) -> Option<PM1Event> {
         let power_switch = power_switch != 0;
+        if let Some(e) = self.estop.update(time, power_switch) {
+            self.events.push_back((time, PM1Event::EStop(e)));
+        }
         if power_switch != self.status.power_switch {
             self.status.power_switch = power_switch;
             Some(PM1Event::PowerSwitch(power_switch))
@@ -296,21 +640,140 @@ impl PM1 {
 
     fn update_odometry(&mut self, time: Instant, which: u8, value: i32) -> Option<PM1Event> {
         if let Some((dl, dr)) = self.differential.update(time, which, value) {
+            let wheels = Wheels {
+                left: Motor::WHEEL.pluses_to_rad(dl),
+                right: Motor::WHEEL.pluses_to_rad(dr),
+            };
+            self.diagnose_wheels(time, wheels);
+            self.trace(time, |time| TraceRecord::Wheels { time, wheels });
             if dl == 0 && dr == 0 {
+                self.battery.update_motion(time, 0.0);
+                self.cruise.update(time, 0.0);
+                self.publish_leader(time);
+                self.validate_odometry(time);
                 None
             } else {
-                Some(PM1Event::Wheels(Wheels {
-                    left: Motor::WHEEL.pluses_to_rad(dl),
-                    right: Motor::WHEEL.pluses_to_rad(dr),
-                }))
+                self.wheels_position.0 += wheels.left;
+                self.wheels_position.1 += wheels.right;
+                self.last_motion = time;
+                let delta = self.model.wheels_to_velocity(wheels);
+                self.odometer.push(delta);
+                self.pose = self.odometer.pose();
+                self.battery.update_motion(time, delta.v);
+                self.cruise.update(time, delta.v);
+                if let Some(calibration) = &mut self.rudder_calibration {
+                    calibration.update(wheels, delta.v);
+                }
+                // 记录历史位姿
+                while let Some((t, _)) = self.poses.front() {
+                    if *t + obstacle::POSE_HISTORY < time {
+                        self.poses.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+                self.poses.push_back((time, self.pose));
+                self.record_route();
+                self.drop_breadcrumb();
+                self.publish_leader(time);
+                self.validate_odometry(time);
+                Some(PM1Event::Wheels(wheels))
             }
         } else {
             None
         }
     }
 
+    /// 按照停止策略生成停止过程中的控制量
+    fn stop_control(
+        &mut self,
+        time: Instant,
+        reason: StopReason,
+        current: &mut Physical,
+    ) -> Option<Control> {
+        const BRAKE: Wheels = Wheels {
+            left: 0.0,
+            right: 0.0,
+        };
+
+        if self.stopping != Some(reason) {
+            self.stopping = Some(reason);
+            self.hold = None;
+        }
+        // 编码器一段时间没有变化，认为底盘已经静止
+        let standstill = time >= self.last_motion + stop::STANDSTILL_TIMEOUT;
+        match self.stop_policy.mode(reason) {
+            StopMode::Coast => {
+                current.speed = 0.0;
+                None
+            }
+            StopMode::Decelerate => {
+                if current.speed == 0.0 {
+                    None
+                } else {
+                    Some(Control::Target(Physical::RELEASED))
+                }
+            }
+            StopMode::Brake => {
+                current.speed = 0.0;
+                if standstill {
+                    None
+                } else {
+                    Some(Control::Wheels(BRAKE))
+                }
+            }
+            StopMode::Hold => {
+                current.speed = 0.0;
+                // 先制动，静止后再记录保持的原点
+                if self.hold.is_none() && standstill {
+                    self.hold = Some(self.wheels_position);
+                }
+                Some(Control::Wheels(match self.hold {
+                    Some(origin) => stop::hold_wheels(origin, self.wheels_position),
+                    None => BRAKE,
+                }))
+            }
+        }
+    }
+
+    /// 编码并发送轮速和后轮目标角度
+    fn send_control(&self, wheels: Wheels, rudder: f32) {
+        let Wheels { left: l, right: r } = wheels;
+        let reply = unsafe {
+            const MSG: [Message; 4] = [
+                message(EVERY_TYPE, EVERY_INDEX, STOP, true),
+                message(ecu::TYPE, 0, ecu::TARGET_SPEED, true),
+                message(ecu::TYPE, 1, ecu::TARGET_SPEED, true),
+                message(tcu::TYPE, 0, tcu::TARGET_POSITION, true),
+            ];
+            const LEN: usize = std::mem::size_of::<Message>();
+
+            let mut msg = MSG;
+            // 控制
+            msg[1]
+                .write()
+                .write_unchecked(Motor::WHEEL.rad_to_pulses(l));
+            msg[2]
+                .write()
+                .write_unchecked(Motor::WHEEL.rad_to_pulses(r));
+            msg[3]
+                .write()
+                .write_unchecked(Motor::RUDDER.rad_to_pulses(rudder - self.rudder_trim) as i16);
+            // 解锁
+            let msg = if self.state_memory.iter().any(|(_, s)| *s == 0xff) {
+                msg[0].write().write_unchecked(0xff as u8);
+                &msg
+            } else {
+                &msg[1..]
+            };
+            std::slice::from_raw_parts(msg.as_ptr() as *const u8, msg.len() * LEN)
+        };
+
+        self.port.write(reply);
+    }
+
     fn update_rudder(&mut self, time: Instant, rudder: i16) -> Option<PM1Event> {
-        let rudder = Motor::RUDDER.pluses_to_rad(rudder.into());
+        let rudder = Motor::RUDDER.pluses_to_rad(rudder.into()) + self.rudder_trim;
         let mut current = self.status.physical;
         // 更新状态
         current.rudder = if rudder > FRAC_PI_2 {
@@ -320,66 +783,120 @@ impl PM1 {
         } else {
             rudder
         };
+        // 辅助驾驶、航点任务和自动驾驶任务
+        self.heading_control(time);
+        self.mission_control(time);
+        self.autopilot_control(time);
+        // 校准过程
+        self.calibration_control(time);
         // 正在使用遥控器，跳过控制
-        let target = if time > self.using_pad + PAD_CONTROL_TIMEOUT {
+        let controlling = time > self.using_pad + PAD_CONTROL_TIMEOUT;
+        let control = if controlling {
             let (deadline, physical) = self.target;
-            if time >= deadline {
+            if !self.status.power_switch || self.estop.active() {
+                // 急停按开关断开，或急停锁存尚未确认
+                self.target = (time, Physical::RELEASED);
+                self.stop_control(time, StopReason::PowerSwitch, &mut current)
+            } else if time >= deadline {
                 // 距离上次接收已经超时
-                if current.speed == 0.0 {
-                    None
+                let reason = if self.stop_requested {
+                    StopReason::Command
                 } else {
-                    Some(Physical::RELEASED)
-                }
-            } else if !self.status.power_switch {
-                // 急停按开关断开
-                self.target = (time, Physical::RELEASED);
-                None
+                    StopReason::Timeout
+                };
+                self.stop_control(time, reason, &mut current)
+            } else if self.battery.level() == BatteryLevel::Critical {
+                // 电量过低
+                self.stop_control(time, StopReason::Battery, &mut current)
+            } else if self.stop_on_rudder_fault && self.rudder_monitor.faulty() {
+                // 后轮故障
+                self.stop_control(time, StopReason::RudderFault, &mut current)
             } else {
-                Some(physical)
+                self.stopping = None;
+                self.hold = None;
+                // 定速巡航，修正后的速度同样经过下面的安全处理
+                let mut physical = physical;
+                physical.speed = self.cruise.correct(time, physical.speed);
+                // 低电量限速
+                if self.battery.level() == BatteryLevel::Limited {
+                    let max = self.battery.policy.limited_speed.abs();
+                    physical.speed = physical.speed.max(-max).min(max);
+                }
+                // 虚拟围栏
+                let (physical, exit) = self.geofence_limit(physical);
+                match exit {
+                    Some(exit) if !self.geofence_violated => {
+                        self.geofence_violated = true;
+                        self.events
+                            .push_back((time, PM1Event::GeofenceViolation(exit)));
+                    }
+                    Some(_) => {}
+                    None => self.geofence_violated = false,
+                }
+                // 障碍物
+                let (physical, ttc) = self.collision_limit(time, physical);
+                match ttc {
+                    Some(ttc) if !self.collision_predicted => {
+                        self.collision_predicted = true;
+                        self.events
+                            .push_back((time, PM1Event::CollisionPredicted(ttc)));
+                    }
+                    Some(_) => {}
+                    None => self.collision_predicted = false,
+                }
+                Some(Control::Target(physical))
             }
         } else {
             None
         };
-        if let Some(mut target) = target {
-            // 执行优化，更新缓存
-            if target.rudder.is_nan() {
-                target.rudder = current.rudder;
+        match control {
+            Some(Control::Target(mut target)) => {
+                // 执行优化，更新缓存
+                if target.rudder.is_nan() {
+                    target.rudder = current.rudder;
+                }
+                let requested = target;
+                self.validate_control(time, Some(requested), current);
+                target.speed = self.optimizer.optimize_speed(target, current);
+                // 施加运行时限制
+                let target = self.limiter.limit(target, current, &self.model);
+                self.cruise.limited(target.speed);
+                self.trace(time, |time| TraceRecord::Control {
+                    time,
+                    target: requested,
+                    sent: target,
+                    rudder: current.rudder,
+                });
+                current.speed = target.speed;
+                let wheels = self.model.physical_to_wheels(current);
+                self.send_control(wheels, target.rudder);
+                self.wheel_monitor
+                    .command(time, Some(wheels), current.rudder);
+                self.diagnose_rudder(time, Some(target.rudder), current.rudder);
             }
-            target.speed = self.optimizer.optimize_speed(target, current);
-            current.speed = target.speed;
-            let Wheels { left: l, right: r } = self.model.physical_to_wheels(current);
-            // 编码
-            let reply = unsafe {
-                const MSG: [Message; 4] = [
-                    message(EVERY_TYPE, EVERY_INDEX, STOP, true),
-                    message(ecu::TYPE, 0, ecu::TARGET_SPEED, true),
-                    message(ecu::TYPE, 1, ecu::TARGET_SPEED, true),
-                    message(tcu::TYPE, 0, tcu::TARGET_POSITION, true),
-                ];
-                const LEN: usize = std::mem::size_of::<Message>();
-
-                let mut msg = MSG;
-                // 控制
-                msg[1]
-                    .write()
-                    .write_unchecked(Motor::WHEEL.rad_to_pulses(l));
-                msg[2]
-                    .write()
-                    .write_unchecked(Motor::WHEEL.rad_to_pulses(r));
-                msg[3]
-                    .write()
-                    .write_unchecked(Motor::RUDDER.rad_to_pulses(target.rudder) as i16);
-                // 解锁
-                let msg = if self.state_memory.iter().any(|(_, s)| *s == 0xff) {
-                    msg[0].write().write_unchecked(0xff as u8);
-                    &msg
-                } else {
-                    &msg[1..]
+            Some(Control::Wheels(wheels)) => {
+                self.validate_control(time, None, current);
+                self.limiter.reset();
+                self.send_control(wheels, current.rudder);
+                self.wheel_monitor
+                    .command(time, Some(wheels), current.rudder);
+                self.diagnose_rudder(time, None, current.rudder);
+            }
+            None => {
+                self.validate_control(time, None, current);
+                self.limiter.reset();
+                // 没有发送控制时，只要不在使用遥控器就认为底盘应当静止
+                let wheels = Wheels {
+                    left: 0.0,
+                    right: 0.0,
                 };
-                std::slice::from_raw_parts(msg.as_ptr() as *const u8, msg.len() * LEN)
-            };
-
-            self.port.write(reply);
+                self.wheel_monitor.command(
+                    time,
+                    Some(wheels).filter(|_| controlling),
+                    current.rudder,
+                );
+                self.diagnose_rudder(time, None, current.rudder);
+            }
         }
         if current != self.status.physical {
             self.status.physical = current;
@@ -396,6 +913,16 @@ impl PM1 {
         let i_node = header.node_index();
         let t_msg = header.msg_type();
 
+        // 节点健康
+        if data {
+            if let Some(node) = self.health.receive(time, t_node, i_node, t_msg) {
+                self.events.push_back((time, PM1Event::NodeRecovered(node)));
+            }
+        }
+        for node in self.health.check(time) {
+            self.events.push_back((time, PM1Event::NodeLost(node)));
+        }
+
         match t_msg {
             // 底盘发送了软件锁定或解锁
             // 这意味着通过遥控器或急停按钮进行了操作
@@ -420,8 +947,10 @@ impl PM1 {
                     // 主动询问
                     vcu::BATTERY_PERCENT => {
                         if data {
-                            self.update_battery_percent(unsafe { msg.read().read_unchecked() })
-                                .and_then(|e| Some((time, e)))
+                            self.update_battery_percent(time, unsafe {
+                                msg.read().read_unchecked()
+                            })
+                            .and_then(|e| Some((time, e)))
                         } else {
                             None
                         }
@@ -430,7 +959,7 @@ impl PM1 {
                     // 主动询问
                     vcu::POWER_SWITCH => {
                         if data {
-                            self.update_power_switch(unsafe { msg.read().read_unchecked() })
+                            self.update_power_switch(time, unsafe { msg.read().read_unchecked() })
                                 .and_then(|e| Some((time, e)))
                         } else {
                             None
@@ -493,6 +1022,14 @@ impl PM1 {
     }
 }
 
+/// 一个控制周期内发往底盘的控制量
+enum Control {
+    /// 经过优化器的目标状态
+    Target(Physical),
+    /// 直接指定的轮速，后轮保持当前角度
+    Wheels(Wheels),
+}
+
 #[inline]
 const fn message(node_type: u8, node_index: u8, msg_type: u8, data_field: bool) -> Message {
     Message::new(0, data_field, 3, node_type, node_index, msg_type)
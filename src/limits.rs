@@ -0,0 +1,254 @@
+use crate::kinematics::unit_velocity;
+use pm1_control_model::{Physical, Pm1Model, Velocity};
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+/// 运行时速度和加速度限制。
+///
+/// 速度单位为 m/s，角度单位为 rad，时间单位为 s。
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// 最大前进速度
+    pub max_forward: f32,
+    /// 最大后退速度（正数）
+    pub max_backward: f32,
+    /// 最大后轮转角
+    pub max_rudder: f32,
+    /// 后轮目标角度的最大变化率
+    pub rudder_rate: f32,
+    /// 最大加速度
+    pub acceleration: f32,
+    /// 最大减速度（正数）
+    pub deceleration: f32,
+    /// 最大横向加速度（m/s²），根据目标曲率限制速度
+    pub lateral_acceleration: f32,
+    /// 最大角速度（rad/s），根据目标曲率限制速度
+    pub yaw_rate: f32,
+}
+
+impl Default for Limits {
+    /// 不做任何限制
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_forward: f32::INFINITY,
+            max_backward: f32::INFINITY,
+            max_rudder: FRAC_PI_2,
+            rudder_rate: f32::INFINITY,
+            acceleration: f32::INFINITY,
+            deceleration: f32::INFINITY,
+            lateral_acceleration: f32::INFINITY,
+            yaw_rate: f32::INFINITY,
+        }
+    }
+}
+
+impl Limits {
+    /// 负数取绝对值，NaN 视为不限制，保证上下界有序
+    pub fn normalized(self) -> Self {
+        #[inline]
+        fn bound(value: f32, default: f32) -> f32 {
+            if value.is_nan() {
+                default
+            } else {
+                value.abs()
+            }
+        }
+
+        let default = Self::default();
+        Self {
+            max_forward: bound(self.max_forward, default.max_forward),
+            max_backward: bound(self.max_backward, default.max_backward),
+            max_rudder: bound(self.max_rudder, default.max_rudder),
+            rudder_rate: bound(self.rudder_rate, default.rudder_rate),
+            acceleration: bound(self.acceleration, default.acceleration),
+            deceleration: bound(self.deceleration, default.deceleration),
+            lateral_acceleration: bound(self.lateral_acceleration, default.lateral_acceleration),
+            yaw_rate: bound(self.yaw_rate, default.yaw_rate),
+        }
+    }
+}
+
+/// 在优化器之后对控制目标施加限制
+#[derive(Clone)]
+pub(crate) struct Limiter {
+    limits: Limits,
+    period: f32,
+    rudder: f32,
+}
+
+impl Limiter {
+    #[inline]
+    pub fn new(period: Duration) -> Self {
+        Self {
+            limits: Default::default(),
+            period: period.as_secs_f32(),
+            rudder: f32::NAN,
+        }
+    }
+
+    #[inline]
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// 更换限制，非法的值按 `Limits::normalized` 修正
+    #[inline]
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits.normalized();
+    }
+
+    /// 限制已经过优化的 `target`，`current` 为上一周期的控制状态
+    pub fn limit(&mut self, target: Physical, current: Physical, model: &Pm1Model) -> Physical {
+        let Limits {
+            max_forward,
+            max_backward,
+            max_rudder,
+            rudder_rate,
+            acceleration,
+            deceleration,
+            lateral_acceleration,
+            yaw_rate,
+        } = self.limits;
+        // 后轮方向
+        let last = if self.rudder.is_nan() {
+            current.rudder
+        } else {
+            self.rudder
+        };
+        let step = rudder_rate * self.period;
+        let rudder = target
+            .rudder
+            .clamp(-max_rudder, max_rudder)
+            .clamp(last - step, last + step);
+        self.rudder = rudder;
+        // 速度
+        let speed = target.speed.clamp(-max_backward, max_forward);
+        let speed = if speed * current.speed < 0.0 || speed.abs() < current.speed.abs() {
+            // 减速或换向
+            let step = deceleration * self.period;
+            speed.clamp(current.speed - step, current.speed + step)
+        } else {
+            let step = acceleration * self.period;
+            speed.clamp(current.speed - step, current.speed + step)
+        };
+        // 曲率，实际角度和目标角度中取更严格的一个
+        let speed = if lateral_acceleration.is_finite() || yaw_rate.is_finite() {
+            let max = f32::min(
+                max_turning_speed(model, current.rudder, lateral_acceleration, yaw_rate),
+                max_turning_speed(model, rudder, lateral_acceleration, yaw_rate),
+            );
+            speed.clamp(-max, max)
+        } else {
+            speed
+        };
+        Physical { speed, rudder }
+    }
+
+    /// 控制中断后，下一次从实际角度开始限制变化率
+    #[inline]
+    pub fn reset(&mut self) {
+        self.rudder = f32::NAN;
+    }
+}
+
+/// 后轮处于 `rudder` 时，满足横向加速度和角速度限制的最大速度
+fn max_turning_speed(model: &Pm1Model, rudder: f32, lateral: f32, yaw: f32) -> f32 {
+    // 速度为 s 时，横向加速度为 s²|vw|，角速度为 s|w|
+    let Velocity { v, w } = unit_velocity(model, rudder);
+    let w = w.abs();
+    let vw = (v * w).abs();
+    f32::min(
+        if vw > 0.0 {
+            (lateral / vw).sqrt()
+        } else {
+            f32::INFINITY
+        },
+        if w > 0.0 { yaw / w } else { f32::INFINITY },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(40);
+
+    fn limiter(limits: Limits) -> Limiter {
+        let mut limiter = Limiter::new(PERIOD);
+        limiter.set_limits(limits);
+        limiter
+    }
+
+    #[inline]
+    fn physical(speed: f32, rudder: f32) -> Physical {
+        Physical { speed, rudder }
+    }
+
+    #[test]
+    fn speed_and_rudder_bounds() {
+        let mut limiter = limiter(Limits {
+            max_forward: 0.5,
+            max_backward: 0.2,
+            max_rudder: 0.3,
+            ..Default::default()
+        });
+        let model = Pm1Model::default();
+        let t = limiter.limit(physical(1.0, 1.0), physical(0.0, 0.0), &model);
+        assert_eq!(t, physical(0.5, 0.3));
+        let t = limiter.limit(physical(-1.0, -1.0), physical(0.0, 0.0), &model);
+        assert_eq!(t, physical(-0.2, -0.3));
+    }
+
+    #[test]
+    fn acceleration_and_deceleration() {
+        let mut limiter = limiter(Limits {
+            acceleration: 1.0,
+            deceleration: 2.0,
+            ..Default::default()
+        });
+        let model = Pm1Model::default();
+        let t = limiter.limit(physical(1.0, 0.0), physical(0.0, 0.0), &model);
+        assert!((t.speed - 0.04).abs() < 1e-6);
+        let t = limiter.limit(physical(0.0, 0.0), physical(1.0, 0.0), &model);
+        assert!((t.speed - 0.92).abs() < 1e-6);
+        // 换向按减速度限制
+        let t = limiter.limit(physical(-1.0, 0.0), physical(0.02, 0.0), &model);
+        assert!((t.speed + 0.06).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rudder_rate_follows_last_target() {
+        let mut limiter = limiter(Limits {
+            rudder_rate: 1.0,
+            ..Default::default()
+        });
+        let model = Pm1Model::default();
+        let t = limiter.limit(physical(0.0, 1.0), physical(0.0, 0.0), &model);
+        assert!((t.rudder - 0.04).abs() < 1e-6);
+        // 从上一次的目标继续变化，而不是实际角度
+        let t = limiter.limit(physical(0.0, 1.0), physical(0.0, 0.0), &model);
+        assert!((t.rudder - 0.08).abs() < 1e-6);
+        limiter.reset();
+        let t = limiter.limit(physical(0.0, 1.0), physical(0.0, 0.5), &model);
+        assert!((t.rudder - 0.54).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_limits_do_not_panic() {
+        let mut limiter = limiter(Limits {
+            max_forward: -1.0,
+            max_backward: -1.0,
+            max_rudder: -0.5,
+            rudder_rate: f32::NAN,
+            acceleration: -1.0,
+            ..Default::default()
+        });
+        let limits = limiter.limits();
+        assert_eq!(limits.max_forward, 1.0);
+        assert_eq!(limits.max_backward, 1.0);
+        assert_eq!(limits.max_rudder, 0.5);
+        assert_eq!(limits.rudder_rate, f32::INFINITY);
+        let t = limiter.limit(physical(2.0, 1.0), physical(1.0, 0.0), &Pm1Model::default());
+        assert_eq!(t, physical(1.0, 0.5));
+    }
+}
@@ -0,0 +1,643 @@
+use crate::{
+    autopilot::{Autopilot, AutopilotResult, Step},
+    calibration::valid_name,
+    motion::Motion,
+    pose::normalize,
+    PM1Event, Pose, PurePursuit, PM1,
+};
+use pm1_control_model::Physical;
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// 未设置存储目录时从此环境变量读取
+const STORE_ENV: &str = "PM1_MISSION_DIR";
+/// 执行任务时保存状态的间隔
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+/// 到达航点后调整航向的角速度（rad/s）
+const TURN_RATE: f32 = 0.5;
+
+lazy_static::lazy_static! {
+    static ref STORE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// 航点，位于里程计坐标系
+#[derive(Clone, PartialEq, Debug)]
+pub struct Waypoint {
+    pub x: f32,
+    pub y: f32,
+    /// 到达后原地旋转到此航向，`None` 表示不调整
+    pub heading: Option<f32>,
+    /// 到达容差（m）
+    pub tolerance: f32,
+    /// 驶向此航点的最大速度（m/s）
+    pub speed: f32,
+    /// 到达后停留的时间
+    pub dwell: Duration,
+    /// 到达后随 `MissionEvent::Arrived` 报告的动作名称，不能为空或 `-`，也不能包含空白字符
+    pub action: Option<String>,
+}
+
+impl Waypoint {
+    /// 以 0.3m/s 驶向 (`x`, `y`)，容差 0.1m，不调整航向也不停留
+    #[inline]
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            heading: None,
+            tolerance: 0.1,
+            speed: 0.3,
+            dwell: Duration::ZERO,
+            action: None,
+        }
+    }
+
+    /// 动作名称能否写入任务文件并原样读回
+    #[inline]
+    fn valid_action(&self) -> bool {
+        self.action
+            .as_deref()
+            .is_none_or(|a| !a.is_empty() && a != "-" && !a.contains(char::is_whitespace))
+    }
+}
+
+/// 任务过程中的事件
+#[derive(Clone, PartialEq, Debug)]
+pub enum MissionEvent {
+    /// 完成一个航点，包括调整航向和停留
+    Arrived {
+        index: usize,
+        action: Option<String>,
+    },
+    Paused,
+    Resumed,
+    /// 所有航点已完成
+    Finished,
+    /// 被 `cancel_mission`、`stop` 或新的任务取消
+    Aborted,
+    /// 无法到达指定序号的航点
+    Failed(usize),
+}
+
+/// 任务的当前状态
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MissionStatus {
+    pub paused: bool,
+    /// 正在前往的航点序号
+    pub index: usize,
+    pub total: usize,
+}
+
+/// 一个航点的执行阶段
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stage {
+    Drive,
+    Turn,
+    Dwell,
+}
+
+impl Stage {
+    #[inline]
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Drive => "drive",
+            Stage::Turn => "turn",
+            Stage::Dwell => "dwell",
+        }
+    }
+
+    #[inline]
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "drive" => Some(Stage::Drive),
+            "turn" => Some(Stage::Turn),
+            "dwell" => Some(Stage::Dwell),
+            _ => None,
+        }
+    }
+}
+
+/// 正在执行的任务。
+///
+/// 每个阶段开始时根据当前位姿重新生成动作，因此暂停和重连后都可以从当前阶段继续。
+pub(crate) struct Mission {
+    waypoints: Vec<Waypoint>,
+    index: usize,
+    stage: Stage,
+    paused: bool,
+    leg: Option<Box<dyn Autopilot>>,
+    dwell_until: Option<Instant>,
+    saved: Option<Instant>,
+}
+
+impl Mission {
+    #[inline]
+    fn new(waypoints: Vec<Waypoint>) -> Self {
+        Self {
+            waypoints,
+            index: 0,
+            stage: Stage::Drive,
+            paused: false,
+            leg: None,
+            dwell_until: None,
+            saved: None,
+        }
+    }
+
+    /// 进入下一阶段，跳过不需要的阶段
+    fn advance(&mut self) {
+        self.leg = None;
+        self.dwell_until = None;
+        let waypoint = &self.waypoints[self.index];
+        self.stage = match self.stage {
+            Stage::Drive if waypoint.heading.is_some() => Stage::Turn,
+            Stage::Drive | Stage::Turn if !waypoint.dwell.is_zero() => Stage::Dwell,
+            _ => {
+                self.index += 1;
+                Stage::Drive
+            }
+        };
+    }
+}
+
+/// 按底盘名称保存正在执行的任务，用于重连后继续执行。
+///
+/// 每台底盘一个文件 `名称.mission`：
+///
+/// ```text
+/// running|paused 航点序号 drive|turn|dwell
+/// pose x y theta
+/// x y 航向|- 容差 速度 停留秒数 动作|-
+/// ...
+/// ```
+pub struct MissionStore;
+
+impl MissionStore {
+    /// 设置存储目录
+    pub fn set_dir(dir: impl Into<PathBuf>) {
+        *STORE_DIR.lock().unwrap() = Some(dir.into());
+    }
+
+    /// 存储目录，未设置时读取环境变量 `PM1_MISSION_DIR`
+    pub fn dir() -> Option<PathBuf> {
+        STORE_DIR
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| std::env::var_os(STORE_ENV).map(PathBuf::from))
+    }
+
+    /// 任务文件路径，未设置存储目录时为 `None`；名称不合法时返回错误，避免写到目录之外
+    fn path(name: &str) -> io::Result<Option<PathBuf>> {
+        if !valid_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid chassis name: {:?}", name),
+            ));
+        }
+        Ok(Self::dir().map(|dir| dir.join(format!("{}.mission", name))))
+    }
+
+    fn save(name: &str, mission: &Mission, pose: Pose) -> io::Result<()> {
+        let path = match Self::path(name)? {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        #[inline]
+        fn optional(value: Option<impl ToString>) -> String {
+            value.map_or_else(|| "-".into(), |v| v.to_string())
+        }
+        let mut text = format!(
+            "{} {} {}\npose {} {} {}\n",
+            if mission.paused { "paused" } else { "running" },
+            mission.index,
+            mission.stage.name(),
+            pose.x,
+            pose.y,
+            pose.theta,
+        );
+        for w in &mission.waypoints {
+            text += &format!(
+                "{} {} {} {} {} {} {}\n",
+                w.x,
+                w.y,
+                optional(w.heading),
+                w.tolerance,
+                w.speed,
+                w.dwell.as_secs_f32(),
+                optional(w.action.as_ref()),
+            );
+        }
+        fs::write(path, text)
+    }
+
+    fn remove(name: &str) -> io::Result<()> {
+        match Self::path(name)?.map(fs::remove_file) {
+            Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn load(name: &str) -> io::Result<Option<(Mission, Pose)>> {
+        let path = match Self::path(name)? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid mission: {}", line),
+            )
+        };
+        let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        let line = lines.next().ok_or_else(|| invalid(""))?;
+        let (paused, index, stage) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [state, index, stage] => (
+                match state {
+                    "running" => false,
+                    "paused" => true,
+                    _ => return Err(invalid(line)),
+                },
+                index.parse().map_err(|_| invalid(line))?,
+                Stage::parse(stage).ok_or_else(|| invalid(line))?,
+            ),
+            _ => return Err(invalid(line)),
+        };
+
+        let line = lines.next().ok_or_else(|| invalid(""))?;
+        let pose = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["pose", x, y, theta] => Pose {
+                x: x.parse().map_err(|_| invalid(line))?,
+                y: y.parse().map_err(|_| invalid(line))?,
+                theta: theta.parse().map_err(|_| invalid(line))?,
+            },
+            _ => return Err(invalid(line)),
+        };
+
+        let mut waypoints = Vec::new();
+        for line in lines {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let number = |s: &str| s.parse::<f32>().map_err(|_| invalid(line));
+            match fields[..] {
+                [x, y, heading, tolerance, speed, dwell, action] => waypoints.push(Waypoint {
+                    x: number(x)?,
+                    y: number(y)?,
+                    heading: match heading {
+                        "-" => None,
+                        s => Some(number(s)?),
+                    },
+                    tolerance: number(tolerance)?,
+                    speed: number(speed)?,
+                    dwell: Duration::try_from_secs_f32(number(dwell)?)
+                        .map_err(|_| invalid(line))?,
+                    action: match action {
+                        "-" => None,
+                        s => Some(s.into()),
+                    },
+                }),
+                _ => return Err(invalid(line)),
+            }
+        }
+        if index >= waypoints.len() {
+            return Ok(None);
+        }
+        let mut mission = Mission::new(waypoints);
+        mission.index = index;
+        mission.stage = stage;
+        mission.paused = paused;
+        Ok(Some((mission, pose)))
+    }
+}
+
+impl PM1 {
+    /// 开始执行航点任务，取消正在执行的任务和自动驾驶任务。
+    ///
+    /// 设置了存储目录时，任务状态和当前位姿会定期保存。
+    /// 重新连接同名底盘后恢复位姿和任务，任务处于暂停状态，
+    /// 确认位姿仍然有效后调用 `resume_mission` 从中断的阶段继续执行。
+    ///
+    /// 航点的动作名称不合法时返回 `InvalidInput` 错误，不影响正在执行的任务。
+    pub fn start_mission(&mut self, waypoints: Vec<Waypoint>) -> io::Result<()> {
+        if let Some(i) = waypoints.iter().position(|w| !w.valid_action()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "invalid action of waypoint {}: {:?}",
+                    i, waypoints[i].action
+                ),
+            ));
+        }
+        let now = Instant::now();
+        self.abort_mission(now);
+        self.abort_autopilot(now);
+        if waypoints.is_empty() {
+            self.events
+                .push_back((now, PM1Event::Mission(MissionEvent::Finished)));
+        } else {
+            self.mission = Some(Mission::new(waypoints));
+            self.save_mission(now);
+        }
+        Ok(())
+    }
+
+    /// 暂停任务并停止，当前阶段在恢复时重新开始
+    pub fn pause_mission(&mut self) {
+        let now = Instant::now();
+        if let Some(mission) = self.mission.as_mut().filter(|m| !m.paused) {
+            mission.paused = true;
+            mission.leg = None;
+            mission.dwell_until = None;
+            self.halt(now);
+            self.events
+                .push_back((now, PM1Event::Mission(MissionEvent::Paused)));
+            self.save_mission(now);
+        }
+    }
+
+    pub fn resume_mission(&mut self) {
+        let now = Instant::now();
+        if let Some(mission) = self.mission.as_mut().filter(|m| m.paused) {
+            mission.paused = false;
+            self.events
+                .push_back((now, PM1Event::Mission(MissionEvent::Resumed)));
+            self.save_mission(now);
+        }
+    }
+
+    /// 取消任务并停止
+    #[inline]
+    pub fn cancel_mission(&mut self) {
+        self.stop();
+    }
+
+    #[inline]
+    pub fn mission_status(&self) -> Option<MissionStatus> {
+        self.mission.as_ref().map(|m| MissionStatus {
+            paused: m.paused,
+            index: m.index,
+            total: m.waypoints.len(),
+        })
+    }
+
+    /// 加载保存的任务，由 `set_name` 调用。
+    ///
+    /// 保存的位姿可能已经过时，因此恢复的任务总是处于暂停状态，产生 `MissionEvent::Paused` 事件。
+    pub(crate) fn restore_mission(&mut self) {
+        let name = match &self.name {
+            Some(name) => name,
+            None => return,
+        };
+        if let Ok(Some((mut mission, pose))) = MissionStore::load(name) {
+            mission.paused = true;
+            self.reset_pose(pose);
+            self.mission = Some(mission);
+            self.events
+                .push_back((Instant::now(), PM1Event::Mission(MissionEvent::Paused)));
+        }
+    }
+
+    /// 取消任务，由 `stop` 调用
+    pub(crate) fn abort_mission(&mut self, time: Instant) {
+        if self.mission.take().is_some() {
+            self.remove_saved_mission();
+            self.events
+                .push_back((time, PM1Event::Mission(MissionEvent::Aborted)));
+        }
+    }
+
+    #[inline]
+    fn remove_saved_mission(&self) {
+        if let Some(name) = &self.name {
+            let _ = MissionStore::remove(name);
+        }
+    }
+
+    fn save_mission(&mut self, time: Instant) {
+        if let Some(mission) = &mut self.mission {
+            mission.saved = Some(time);
+            if let Some(name) = &self.name {
+                let _ = MissionStore::save(name, mission, self.pose);
+            }
+        }
+    }
+
+    /// 结束任务，产生事件并删除保存的状态
+    fn finish_mission(&mut self, time: Instant, event: MissionEvent) {
+        self.mission = None;
+        self.remove_saved_mission();
+        self.halt(time);
+        self.events.push_back((time, PM1Event::Mission(event)));
+    }
+
+    /// 每个控制周期推进任务
+    pub(crate) fn mission_control(&mut self, time: Instant) {
+        let mut mission = match self.mission.take() {
+            Some(m) if !m.paused => m,
+            other => {
+                self.mission = other;
+                return;
+            }
+        };
+        let waypoint = mission.waypoints[mission.index].clone();
+        let step = match mission.stage {
+            Stage::Dwell => {
+                let until = *mission.dwell_until.get_or_insert(time + waypoint.dwell);
+                if time >= until {
+                    Step::Finish(AutopilotResult::Finished)
+                } else {
+                    Step::Drive(Physical::RELEASED)
+                }
+            }
+            stage => {
+                let pose = self.pose;
+                let leg = mission.leg.get_or_insert_with(|| match stage {
+                    Stage::Turn => Box::new(Motion::rotate(
+                        normalize(waypoint.heading.unwrap_or(pose.theta) - pose.theta),
+                        TURN_RATE,
+                    )),
+                    // 航点在身后时跟随器先原地转向航点
+                    _ => Box::new(
+                        PurePursuit::new(vec![(waypoint.x, waypoint.y)], waypoint.speed)
+                            .with_tolerance(waypoint.tolerance),
+                    ),
+                });
+                leg.step(time, self)
+            }
+        };
+        match step {
+            Step::Drive(target) => {
+                self.set_target((time, target));
+                let save = mission.saved.is_none_or(|t| time >= t + SAVE_INTERVAL);
+                self.mission = Some(mission);
+                if save {
+                    self.save_mission(time);
+                }
+            }
+            Step::Finish(AutopilotResult::Finished) => {
+                let index = mission.index;
+                mission.advance();
+                if mission.index != index {
+                    self.events.push_back((
+                        time,
+                        PM1Event::Mission(MissionEvent::Arrived {
+                            index,
+                            action: waypoint.action,
+                        }),
+                    ));
+                }
+                if mission.index == mission.waypoints.len() {
+                    self.finish_mission(time, MissionEvent::Finished);
+                } else {
+                    self.mission = Some(mission);
+                    self.save_mission(time);
+                }
+            }
+            Step::Finish(_) => {
+                let index = mission.index;
+                self.finish_mission(time, MissionEvent::Failed(index));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 所有测试共用一个临时目录，以不同的底盘名称区分
+    fn store() {
+        let dir = std::env::temp_dir().join(format!("pm1-mission-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        MissionStore::set_dir(dir);
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![
+            Waypoint::new(1.0, 0.0),
+            Waypoint {
+                heading: Some(1.5),
+                tolerance: 0.2,
+                speed: 0.5,
+                dwell: Duration::from_millis(2500),
+                action: Some("scan".into()),
+                ..Waypoint::new(1.0, 2.0)
+            },
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        store();
+        let mut mission = Mission::new(waypoints());
+        mission.index = 1;
+        mission.stage = Stage::Turn;
+        let pose = Pose {
+            x: 1.0,
+            y: 1.5,
+            theta: 0.25,
+        };
+        MissionStore::save("round-trip", &mission, pose).unwrap();
+
+        let (loaded, loaded_pose) = MissionStore::load("round-trip").unwrap().unwrap();
+        assert_eq!(loaded.waypoints, mission.waypoints);
+        assert_eq!(loaded.index, 1);
+        assert_eq!(loaded.stage, Stage::Turn);
+        assert!(!loaded.paused);
+        assert_eq!(loaded_pose, pose);
+
+        MissionStore::remove("round-trip").unwrap();
+        assert!(MissionStore::load("round-trip").unwrap().is_none());
+        // 文件不存在时删除不报错
+        MissionStore::remove("round-trip").unwrap();
+    }
+
+    #[test]
+    fn validate_actions() {
+        for action in ["scan", "pick-up", "放置"] {
+            let w = Waypoint {
+                action: Some(action.into()),
+                ..Waypoint::new(0.0, 0.0)
+            };
+            assert!(w.valid_action(), "{:?}", action);
+        }
+        for action in ["", "-", "pick up", "scan\n", "\tscan"] {
+            let w = Waypoint {
+                action: Some(action.into()),
+                ..Waypoint::new(0.0, 0.0)
+            };
+            assert!(!w.valid_action(), "{:?}", action);
+        }
+        assert!(Waypoint::new(0.0, 0.0).valid_action());
+    }
+
+    #[test]
+    fn round_trip_actions() {
+        store();
+        let waypoints = ["scan", "pick-up", "放置"]
+            .into_iter()
+            .map(|action| Waypoint {
+                action: Some(action.into()),
+                ..Waypoint::new(0.0, 0.0)
+            })
+            .chain(std::iter::once(Waypoint::new(1.0, 0.0)))
+            .collect::<Vec<_>>();
+        let mission = Mission::new(waypoints);
+        MissionStore::save("actions", &mission, Pose::ZERO).unwrap();
+        let (loaded, _) = MissionStore::load("actions").unwrap().unwrap();
+        assert_eq!(loaded.waypoints, mission.waypoints);
+        MissionStore::remove("actions").unwrap();
+    }
+
+    #[test]
+    fn paused_state() {
+        store();
+        let mut mission = Mission::new(waypoints());
+        mission.paused = true;
+        MissionStore::save("paused", &mission, Pose::ZERO).unwrap();
+        let (loaded, _) = MissionStore::load("paused").unwrap().unwrap();
+        assert!(loaded.paused);
+        MissionStore::remove("paused").unwrap();
+    }
+
+    #[test]
+    fn reject_invalid_names() {
+        store();
+        let mission = Mission::new(waypoints());
+        for name in ["", "..", "/tmp/escape", "a/b", "a b"] {
+            let e = MissionStore::save(name, &mission, Pose::ZERO).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_files() {
+        store();
+        let path = MissionStore::path("invalid").unwrap().unwrap();
+        for text in [
+            "",
+            "stopped 0 drive\npose 0 0 0\n1 0 - 0.1 0.3 0 -\n",
+            "running 0 fly\npose 0 0 0\n1 0 - 0.1 0.3 0 -\n",
+            "running 0 drive\n1 0 - 0.1 0.3 0 -\n",
+            "running 0 drive\npose 0 0 0\n1 0 - 0.1 0.3 inf -\n",
+            "running 0 drive\npose 0 0 0\n1 0 - 0.1\n",
+        ] {
+            fs::write(&path, text).unwrap();
+            let e = MissionStore::load("invalid").err().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData, "{:?}", text);
+        }
+        // 序号超出航点数量视为没有任务
+        fs::write(&path, "running 1 drive\npose 0 0 0\n1 0 - 0.1 0.3 0 -\n").unwrap();
+        assert!(MissionStore::load("invalid").unwrap().is_none());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{
+    autopilot::{Autopilot, AutopilotResult, Step},
+    kinematics::{rudder_for_curvature, unit_velocity},
+    path::{profile, DECELERATION, MIN_SPEED},
+    pose::normalize,
+    Pose, PM1,
+};
+use pm1_control_model::Physical;
+use std::{
+    f32::consts::FRAC_PI_2,
+    time::{Duration, Instant},
+};
+
+/// 默认的行驶速度（m/s）
+const DEFAULT_SPEED: f32 = 0.3;
+/// 默认的原地旋转角速度（rad/s）
+const DEFAULT_RATE: f32 = 0.5;
+/// 接近终点时的角减速度（rad/s²）
+const ANGULAR_DECELERATION: f32 = 0.5;
+/// 接近终点时的最低角速度（rad/s）
+const MIN_RATE: f32 = 0.05;
+/// 到达终点的距离容差（m）
+const DISTANCE_TOLERANCE: f32 = 0.01;
+/// 到达终点的角度容差（rad）
+const ANGLE_TOLERANCE: f32 = 0.01;
+/// 默认超时在标称时间之外的余量
+const TIMEOUT_MARGIN: Duration = Duration::from_secs(3);
+/// 超时的上限，避免计算截止时间时溢出
+const MAX_TIMEOUT: Duration = Duration::from_secs(u32::MAX as u64);
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Distance(f32),
+    Rotate(f32),
+    Arc { radius: f32, angle: f32 },
+}
+
+/// 根据编码器里程计闭环的基本动作：直行、原地旋转和圆弧。
+///
+/// 接近终点时按固定减速度降速，越过终点或到达容差范围内即结束。
+#[derive(Clone, Debug)]
+pub struct Motion {
+    kind: Kind,
+    speed: f32,
+    timeout: Duration,
+
+    deadline: Option<Instant>,
+    origin: Option<Pose>,
+    theta: f32,
+    turned: f32,
+}
+
+impl Motion {
+    /// 直行 `meters`（负数后退），`speed` 为最大速度（m/s）
+    pub fn distance(meters: f32, speed: f32) -> Self {
+        let speed = speed.abs();
+        Self::new(Kind::Distance(meters), speed, meters.abs() / speed)
+    }
+
+    /// 后轮转到 π/2 原地旋转 `angle`（rad，逆时针为正），`rate` 为最大角速度（rad/s）
+    pub fn rotate(angle: f32, rate: f32) -> Self {
+        let rate = rate.abs();
+        Self::new(Kind::Rotate(angle), rate, angle.abs() / rate)
+    }
+
+    /// 沿半径 `radius`（m）的圆弧前进，航向改变 `angle`（rad，左转为正），`speed` 为最大速度（m/s）
+    pub fn arc(radius: f32, angle: f32, speed: f32) -> Self {
+        let radius = radius.abs();
+        let speed = speed.abs();
+        Self::new(
+            Kind::Arc { radius, angle },
+            speed,
+            radius * angle.abs() / speed,
+        )
+    }
+
+    /// 修改超时，默认为标称时间的两倍再加 3s
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout.min(MAX_TIMEOUT);
+        self
+    }
+
+    fn new(kind: Kind, speed: f32, nominal: f32) -> Self {
+        let nominal = if nominal.is_finite() { nominal } else { 0.0 };
+        Self {
+            kind,
+            speed,
+            timeout: Duration::try_from_secs_f32(nominal * 2.0)
+                .map_or(MAX_TIMEOUT, |t| t.saturating_add(TIMEOUT_MARGIN))
+                .min(MAX_TIMEOUT),
+
+            deadline: None,
+            origin: None,
+            theta: 0.0,
+            turned: 0.0,
+        }
+    }
+}
+
+/// 剩余量进入容差或越过终点时视为完成
+#[inline]
+fn reached(remaining: f32, total: f32, tolerance: f32) -> bool {
+    remaining.abs() < tolerance || remaining.signum() != total.signum()
+}
+
+impl Autopilot for Motion {
+    fn step(&mut self, time: Instant, pm1: &PM1) -> Step {
+        let pose = pm1.pose();
+        let deadline = *self.deadline.get_or_insert(time + self.timeout);
+        let origin = *self.origin.get_or_insert_with(|| {
+            self.theta = pose.theta;
+            pose
+        });
+        if time >= deadline {
+            return Step::Finish(AutopilotResult::TimedOut);
+        }
+        // 累计航向变化，允许超过半圈
+        self.turned += normalize(pose.theta - self.theta);
+        self.theta = pose.theta;
+
+        let target = match self.kind {
+            Kind::Distance(distance) => {
+                let (travelled, _) = origin.inverse_transform((pose.x, pose.y));
+                let remaining = distance - travelled;
+                if reached(remaining, distance, DISTANCE_TOLERANCE) {
+                    return Step::Finish(AutopilotResult::Finished);
+                }
+                Physical {
+                    speed: profile(remaining.abs(), self.speed, DECELERATION, MIN_SPEED)
+                        * remaining.signum(),
+                    rudder: 0.0,
+                }
+            }
+            Kind::Rotate(angle) => {
+                let remaining = angle - self.turned;
+                if reached(remaining, angle, ANGLE_TOLERANCE) {
+                    return Step::Finish(AutopilotResult::Finished);
+                }
+                let rudder = FRAC_PI_2;
+                let unit = unit_velocity(&pm1.model, rudder);
+                let rate = profile(remaining.abs(), self.speed, ANGULAR_DECELERATION, MIN_RATE);
+                Physical {
+                    speed: rate / unit.w.abs() * remaining.signum() * unit.w.signum(),
+                    rudder,
+                }
+            }
+            Kind::Arc { radius, angle } => {
+                let remaining = angle - self.turned;
+                if reached(remaining, angle, ANGLE_TOLERANCE) {
+                    return Step::Finish(AutopilotResult::Finished);
+                }
+                Physical {
+                    speed: profile(
+                        remaining.abs() * radius,
+                        self.speed,
+                        DECELERATION,
+                        MIN_SPEED,
+                    ),
+                    rudder: rudder_for_curvature(&pm1.model, angle.signum() / radius),
+                }
+            }
+        };
+        Step::Drive(target)
+    }
+}
+
+impl PM1 {
+    /// 直行 `meters`（负数后退），不阻塞，完成时产生 `PM1Event::AutopilotFinished` 事件
+    #[inline]
+    pub fn move_distance(&mut self, meters: f32, speed: f32) {
+        self.start_autopilot(Motion::distance(meters, speed));
+    }
+
+    /// 以默认角速度原地旋转 `angle`（rad，逆时针为正），不阻塞
+    #[inline]
+    pub fn rotate(&mut self, angle: f32) {
+        self.start_autopilot(Motion::rotate(angle, DEFAULT_RATE));
+    }
+
+    /// 以默认速度沿半径 `radius` 的圆弧前进，航向改变 `angle`（rad，左转为正），不阻塞
+    #[inline]
+    pub fn arc(&mut self, radius: f32, angle: f32) {
+        self.start_autopilot(Motion::arc(radius, angle, DEFAULT_SPEED));
+    }
+
+    /// 阻塞地直行，连接断开时返回 `None`，期间的其他事件被丢弃
+    #[inline]
+    pub fn move_distance_blocking(&mut self, meters: f32, speed: f32) -> Option<AutopilotResult> {
+        self.run(Motion::distance(meters, speed), |_, _| {})
+    }
+
+    /// 阻塞地原地旋转，连接断开时返回 `None`，期间的其他事件被丢弃
+    #[inline]
+    pub fn rotate_blocking(&mut self, angle: f32) -> Option<AutopilotResult> {
+        self.run(Motion::rotate(angle, DEFAULT_RATE), |_, _| {})
+    }
+
+    /// 阻塞地沿圆弧前进，连接断开时返回 `None`，期间的其他事件被丢弃
+    #[inline]
+    pub fn arc_blocking(&mut self, radius: f32, angle: f32) -> Option<AutopilotResult> {
+        self.run(Motion::arc(radius, angle, DEFAULT_SPEED), |_, _| {})
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    autopilot::{Autopilot, Step},
+    path::{profile, Tracker, DECELERATION, MIN_SPEED},
+    ControlPredictor, Footprint, Obstacle, CONTROL_PERIOD, PM1,
+};
+use pm1_control_model::Physical;
+use std::time::{Duration, Instant};
+
+/// 默认的推演时长
+const HORIZON: Duration = Duration::from_millis(1500);
+/// 候选速度相对于允许速度的比例
+const SPEED_SCALES: [f32; 3] = [1.0, 0.6, 0.3];
+/// 候选后轮角度的数量，在 ±最大角度之间均匀分布
+const RUDDER_SAMPLES: usize = 15;
+/// 候选后轮角度的上限（rad）
+const MAX_RUDDER: f32 = 1.2;
+/// 默认的安全距离（m），与障碍物的距离小于此值时计入代价
+const SAFE_DISTANCE: f32 = 0.5;
+
+/// 代价函数的权重
+#[derive(Clone, Copy, Debug)]
+pub struct MpcWeights {
+    /// 推演过程中偏离路径的平均距离（m）
+    pub deviation: f32,
+    /// 推演过程中进入安全距离的平均深度（m）
+    pub obstacle: f32,
+    /// 与上一周期所选目标的差异
+    pub smoothness: f32,
+    /// 推演结束时沿路径前进的距离（m），计为负代价
+    pub progress: f32,
+}
+
+impl Default for MpcWeights {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            deviation: 2.0,
+            obstacle: 10.0,
+            smoothness: 0.5,
+            progress: 1.0,
+        }
+    }
+}
+
+/// 采样式模型预测控制器。
+///
+/// 每个控制周期在允许的速度和后轮角度范围内采样候选目标，
+/// 用 `PM1::control_predictor` 推演一段时间，按路径偏离、障碍物距离、平滑性和前进距离计算代价，
+/// 选择代价最小且不会碰撞的目标；所有候选都会碰撞时原地等待。
+pub struct Mpc {
+    tracker: Tracker,
+    speed: f32,
+    weights: MpcWeights,
+    horizon: usize,
+    safe_distance: f32,
+
+    last: Option<Physical>,
+}
+
+impl Mpc {
+    /// 以最大速度 `speed`（m/s）跟随里程计坐标系下的 `path`，只有一个点时从当前位置驶向该点
+    pub fn new(path: Vec<(f32, f32)>, speed: f32) -> Self {
+        Self {
+            tracker: Tracker::new(path),
+            speed: speed.abs(),
+            weights: Default::default(),
+            horizon: (HORIZON.as_millis() / CONTROL_PERIOD.as_millis()) as usize,
+            safe_distance: SAFE_DISTANCE,
+
+            last: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_weights(mut self, weights: MpcWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// 修改推演时长，默认 1.5s
+    #[inline]
+    pub fn with_horizon(mut self, horizon: Duration) -> Self {
+        self.horizon = ((horizon.as_millis() / CONTROL_PERIOD.as_millis()) as usize).max(1);
+        self
+    }
+
+    /// 修改安全距离（m）
+    #[inline]
+    pub fn with_safe_distance(mut self, distance: f32) -> Self {
+        self.safe_distance = distance;
+        self
+    }
+
+    /// 修改终点容差（m）
+    #[inline]
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tracker.tolerance = tolerance;
+        self
+    }
+
+    /// 设置超时，默认不限时间
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.tracker.timeout = Some(timeout);
+        self
+    }
+
+    /// 推演候选目标，会碰撞时返回 `None`
+    fn cost(
+        &self,
+        predictor: &ControlPredictor,
+        obstacles: &[Obstacle],
+        footprint: Footprint,
+        target: Physical,
+        last: Physical,
+    ) -> Option<f32> {
+        let path = self.tracker.path();
+        let travelled = self.tracker.travelled();
+        let mut segment = self.tracker.segment();
+        let mut deviation = 0.0;
+        let mut intrusion = 0.0;
+        let mut reached = travelled;
+        for (pose, _) in predictor.clone().with_target(target).take(self.horizon) {
+            let (i, s, d) = path.project((pose.x, pose.y), segment);
+            segment = i;
+            reached = s;
+            deviation += d;
+            for o in obstacles {
+                let clearance = footprint.clearance(&o.transform(|p| pose.inverse_transform(p)));
+                if clearance == 0.0 {
+                    return None;
+                }
+                intrusion += (self.safe_distance - clearance).max(0.0);
+            }
+        }
+        let n = self.horizon as f32;
+        let w = &self.weights;
+        Some(
+            w.deviation * deviation / n
+                + w.obstacle * intrusion / n
+                + w.smoothness
+                    * ((target.speed - last.speed).powi(2) + (target.rudder - last.rudder).powi(2))
+                - w.progress * (reached - travelled),
+        )
+    }
+}
+
+impl Autopilot for Mpc {
+    fn step(&mut self, time: Instant, pm1: &PM1) -> Step {
+        let (_, remaining) = match self.tracker.update(time, pm1.pose()) {
+            Ok(tracking) => tracking,
+            Err(result) => return Step::Finish(result),
+        };
+
+        let speed = profile(remaining, self.speed, DECELERATION, MIN_SPEED);
+        let max_rudder = pm1.limits().max_rudder.min(MAX_RUDDER);
+        let last = self.last.unwrap_or(pm1.status().physical);
+        let predictor = pm1.control_predictor();
+        let obstacles = pm1.current_obstacles(time);
+        let footprint = pm1.footprint();
+        let best = SPEED_SCALES
+            .iter()
+            .flat_map(|k| {
+                (0..RUDDER_SAMPLES).map(move |i| Physical {
+                    speed: speed * k,
+                    rudder: max_rudder * (2.0 * i as f32 / (RUDDER_SAMPLES - 1) as f32 - 1.0),
+                })
+            })
+            .filter_map(|target| {
+                let cost = self.cost(&predictor, obstacles, footprint, target, last)?;
+                Some((cost, target))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, target)| target);
+        let target = best.unwrap_or(Physical {
+            speed: 0.0,
+            rudder: last.rudder,
+        });
+        self.last = Some(target);
+        Step::Drive(target)
+    }
+
+    #[inline]
+    fn progress(&mut self) -> Option<f32> {
+        self.tracker.progress()
+    }
+}
+
+impl PM1 {
+    /// 使用模型预测控制器以最大速度 `speed` 跟随路径并避开障碍物，不阻塞
+    #[inline]
+    pub fn follow_path_mpc(&mut self, path: Vec<(f32, f32)>, speed: f32) {
+        self.start_autopilot(Mpc::new(path, speed));
+    }
+}
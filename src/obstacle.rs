@@ -0,0 +1,289 @@
+use crate::{
+    geometry::{polygon_contains, polygon_distance, segments_intersect},
+    predict::scale_down,
+    Pose, CONTROL_PERIOD, PM1,
+};
+use pm1_control_model::Physical;
+use std::time::{Duration, Instant};
+
+/// 检查碰撞时向前推演的时长
+const HORIZON: Duration = Duration::from_millis(1200);
+/// 障碍物超过此时间未更新则不再使用
+const OBSTACLE_TIMEOUT: Duration = Duration::from_millis(500);
+/// 用于查询历史位姿的记录时长
+pub(crate) const POSE_HISTORY: Duration = Duration::from_secs(2);
+
+/// 二维障碍物。
+///
+/// 传入 `PM1::set_obstacles` 时位于机器人坐标系，保存时变换到里程计坐标系。
+#[derive(Clone, Debug)]
+pub enum Obstacle {
+    Point((f32, f32)),
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+    },
+    /// 多边形，顶点按顺序排列
+    Polygon(Vec<(f32, f32)>),
+}
+
+/// 机器人坐标系下的矩形底盘轮廓
+#[derive(Clone, Copy, Debug)]
+pub struct Footprint {
+    pub front: f32,
+    pub back: f32,
+    pub left: f32,
+    pub right: f32,
+    /// 额外保留的安全距离
+    pub margin: f32,
+}
+
+impl Default for Footprint {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            front: 0.25,
+            back: 0.6,
+            left: 0.3,
+            right: 0.3,
+            margin: 0.1,
+        }
+    }
+}
+
+impl Obstacle {
+    pub(crate) fn transform(&self, f: impl Fn((f32, f32)) -> (f32, f32)) -> Self {
+        match self {
+            Self::Point(p) => Self::Point(f(*p)),
+            Self::Circle { center, radius } => Self::Circle {
+                center: f(*center),
+                radius: *radius,
+            },
+            Self::Polygon(vertices) => Self::Polygon(vertices.iter().map(|p| f(*p)).collect()),
+        }
+    }
+}
+
+impl Footprint {
+    /// 包含安全距离的矩形顶点，逆时针排列
+    fn corners(&self) -> [(f32, f32); 4] {
+        let Self {
+            front,
+            back,
+            left,
+            right,
+            margin,
+        } = *self;
+        [
+            (front + margin, left + margin),
+            (-back - margin, left + margin),
+            (-back - margin, -right - margin),
+            (front + margin, -right - margin),
+        ]
+    }
+
+    /// 点到轮廓的距离，在轮廓内为零
+    fn distance(&self, p: (f32, f32)) -> f32 {
+        let corners = self.corners();
+        let dx = f32::max(corners[1].0 - p.0, p.0 - corners[0].0).max(0.0);
+        let dy = f32::max(corners[2].1 - p.1, p.1 - corners[0].1).max(0.0);
+        dx.hypot(dy)
+    }
+
+    /// 判断机器人坐标系下的障碍物是否与轮廓重叠
+    fn collides(&self, obstacle: &Obstacle) -> bool {
+        match obstacle {
+            Obstacle::Point(p) => self.distance(*p) == 0.0,
+            Obstacle::Circle { center, radius } => self.distance(*center) <= *radius,
+            Obstacle::Polygon(vertices) => {
+                let corners = self.corners();
+                vertices.iter().any(|p| self.distance(*p) == 0.0)
+                    || corners.iter().any(|c| polygon_contains(vertices, *c))
+                    || (0..vertices.len()).any(|i| {
+                        let (a, b) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+                        (0..4).any(|j| segments_intersect(a, b, corners[j], corners[(j + 1) % 4]))
+                    })
+            }
+        }
+    }
+
+    /// 机器人坐标系下的障碍物到轮廓的距离，重叠时为零
+    pub(crate) fn clearance(&self, obstacle: &Obstacle) -> f32 {
+        match obstacle {
+            Obstacle::Point(p) => self.distance(*p),
+            Obstacle::Circle { center, radius } => (self.distance(*center) - radius).max(0.0),
+            Obstacle::Polygon(vertices) => {
+                if self.collides(obstacle) {
+                    0.0
+                } else {
+                    let to_vertices = vertices.iter().map(|p| self.distance(*p));
+                    let to_corners = self
+                        .corners()
+                        .into_iter()
+                        .map(|c| polygon_distance(vertices, c));
+                    to_vertices.chain(to_corners).fold(f32::INFINITY, f32::min)
+                }
+            }
+        }
+    }
+}
+
+impl PM1 {
+    /// 更新障碍物列表，`time` 为障碍物的观测时刻，坐标位于该时刻的机器人坐标系
+    pub fn set_obstacles(&mut self, time: Instant, obstacles: Vec<Obstacle>) {
+        let pose = self.pose_at(time);
+        self.obstacles = Some((
+            time,
+            obstacles
+                .iter()
+                .map(|o| o.transform(|p| pose.transform(p)))
+                .collect(),
+        ));
+    }
+
+    #[inline]
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles = None;
+        self.collision_predicted = false;
+    }
+
+    #[inline]
+    pub fn footprint(&self) -> Footprint {
+        self.footprint
+    }
+
+    #[inline]
+    pub fn set_footprint(&mut self, footprint: Footprint) {
+        self.footprint = footprint;
+    }
+
+    /// 查询某一时刻的位姿，超出记录范围时使用最早的记录
+    pub(crate) fn pose_at(&self, time: Instant) -> Pose {
+        self.poses
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= time)
+            .or_else(|| self.poses.front())
+            .map_or(self.pose, |(_, pose)| *pose)
+    }
+
+    /// 尚未过期的障碍物，位于里程计坐标系
+    pub(crate) fn current_obstacles(&self, time: Instant) -> &[Obstacle] {
+        match &self.obstacles {
+            Some((t, obstacles)) if time < *t + OBSTACLE_TIMEOUT => obstacles,
+            _ => &[],
+        }
+    }
+
+    /// 推演 `target`，返回预测发生碰撞前的时长
+    fn time_to_collision(&self, obstacles: &[Obstacle], target: Physical) -> Option<Duration> {
+        let steps = (HORIZON.as_millis() / CONTROL_PERIOD.as_millis()) as u32;
+        self.control_predictor()
+            .with_target(target)
+            .take(steps as usize)
+            .position(|(pose, _)| {
+                obstacles
+                    .iter()
+                    .map(|o| o.transform(|p| pose.inverse_transform(p)))
+                    .any(|o| self.footprint.collides(&o))
+            })
+            // 第一个推演位姿已经是一个控制周期之后
+            .map(|i| CONTROL_PERIOD * (i as u32 + 1))
+    }
+
+    /// 根据障碍物修正控制目标，返回修正后的目标和预测的碰撞时间
+    pub(crate) fn collision_limit(
+        &self,
+        time: Instant,
+        target: Physical,
+    ) -> (Physical, Option<Duration>) {
+        let obstacles = self.current_obstacles(time);
+        if obstacles.is_empty() {
+            return (target, None);
+        }
+        match self.time_to_collision(obstacles, target) {
+            Some(ttc) => (
+                scale_down(target, |t| self.time_to_collision(obstacles, t).is_none()),
+                Some(ttc),
+            ),
+            None => (target, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 前后各 1m、左右各 0.5m，没有安全距离
+    const FOOTPRINT: Footprint = Footprint {
+        front: 1.0,
+        back: 1.0,
+        left: 0.5,
+        right: 0.5,
+        margin: 0.0,
+    };
+
+    #[test]
+    fn point() {
+        assert!(FOOTPRINT.collides(&Obstacle::Point((0.9, 0.4))));
+        assert!(!FOOTPRINT.collides(&Obstacle::Point((1.1, 0.0))));
+        assert!((FOOTPRINT.clearance(&Obstacle::Point((2.0, 0.0))) - 1.0).abs() < 1e-6);
+        // 安全距离扩大轮廓
+        let footprint = Footprint {
+            margin: 0.2,
+            ..FOOTPRINT
+        };
+        assert!(footprint.collides(&Obstacle::Point((1.1, 0.0))));
+    }
+
+    #[test]
+    fn circle() {
+        let circle = |x, y| Obstacle::Circle {
+            center: (x, y),
+            radius: 0.5,
+        };
+        assert!(FOOTPRINT.collides(&circle(1.4, 0.0)));
+        assert!(!FOOTPRINT.collides(&circle(1.6, 0.0)));
+        // 角点附近按欧氏距离计算
+        assert!(!FOOTPRINT.collides(&circle(1.4, 0.9)));
+        assert!((FOOTPRINT.clearance(&circle(2.0, 0.0)) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn polygon() {
+        // 顶点落在轮廓内
+        let inside = Obstacle::Polygon(vec![(0.5, 0.0), (2.0, 0.0), (2.0, 1.0)]);
+        assert!(FOOTPRINT.collides(&inside));
+        // 轮廓完全在多边形内
+        let around = Obstacle::Polygon(vec![(-5.0, -5.0), (5.0, -5.0), (5.0, 5.0), (-5.0, 5.0)]);
+        assert!(FOOTPRINT.collides(&around));
+        // 没有顶点在对方内部，但边相交
+        let across = Obstacle::Polygon(vec![(-0.2, -2.0), (0.2, -2.0), (0.2, 2.0), (-0.2, 2.0)]);
+        assert!(FOOTPRINT.collides(&across));
+        // 分离
+        let apart = Obstacle::Polygon(vec![(2.0, -1.0), (3.0, -1.0), (3.0, 1.0), (2.0, 1.0)]);
+        assert!(!FOOTPRINT.collides(&apart));
+        assert!((FOOTPRINT.clearance(&apart) - 1.0).abs() < 1e-6);
+        assert_eq!(FOOTPRINT.clearance(&across), 0.0);
+    }
+
+    #[test]
+    fn transform_to_robot_frame() {
+        let pose = Pose {
+            x: 1.0,
+            y: 0.0,
+            theta: std::f32::consts::FRAC_PI_2,
+        };
+        // 里程计坐标系下位于机器人正前方 1.5m
+        let obstacle = Obstacle::Point((1.0, 1.5)).transform(|p| pose.inverse_transform(p));
+        match obstacle {
+            Obstacle::Point((x, y)) => {
+                assert!((x - 1.5).abs() < 1e-5);
+                assert!(y.abs() < 1e-5);
+            }
+            _ => unreachable!(),
+        }
+        assert!(!FOOTPRINT.collides(&obstacle));
+    }
+}
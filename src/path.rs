@@ -0,0 +1,216 @@
+use crate::{autopilot::AutopilotResult, Pose};
+use std::time::{Duration, Instant};
+
+/// 每次向前搜索最近点的线段数
+const SEARCH: usize = 8;
+/// 进度报告的间隔
+const PROGRESS_STEP: f32 = 0.1;
+/// 默认的终点容差（m）
+pub(crate) const TOLERANCE: f32 = 0.05;
+/// 接近终点时的减速度（m/s²）
+pub(crate) const DECELERATION: f32 = 0.3;
+/// 接近终点时的最低速度（m/s），避免停在终点之前
+pub(crate) const MIN_SPEED: f32 = 0.03;
+
+/// 剩余 `remaining` 时允许的速度
+#[inline]
+pub(crate) fn profile(remaining: f32, max: f32, deceleration: f32, min: f32) -> f32 {
+    max.min((2.0 * deceleration * remaining).sqrt()).max(min)
+}
+
+/// 里程计坐标系下的折线路径，预先计算各顶点处的弧长
+pub(crate) struct Path {
+    points: Vec<(f32, f32)>,
+    lengths: Vec<f32>,
+}
+
+impl Path {
+    pub fn new(points: Vec<(f32, f32)>) -> Self {
+        let mut length = 0.0;
+        let lengths = std::iter::once(0.0)
+            .chain(points.windows(2).map(|w| {
+                length += (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1);
+                length
+            }))
+            .collect();
+        Self { points, lengths }
+    }
+
+    #[inline]
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// 路径总长度（m）
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.lengths.last().copied().unwrap_or(0.0)
+    }
+
+    #[inline]
+    pub fn end(&self) -> Option<(f32, f32)> {
+        self.points.last().copied()
+    }
+
+    /// 路径上弧长 `s` 处的点，路径不能为空
+    pub fn point_at(&self, s: f32) -> (f32, f32) {
+        if self.points.len() == 1 {
+            return self.points[0];
+        }
+        let i = self
+            .lengths
+            .partition_point(|l| *l <= s)
+            .clamp(1, self.points.len() - 1);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        let len = self.lengths[i] - self.lengths[i - 1];
+        let k = if len > 0.0 {
+            ((s - self.lengths[i - 1]) / len).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        (a.0 + (b.0 - a.0) * k, a.1 + (b.1 - a.1) * k)
+    }
+
+    /// 从第 `segment` 段开始向前搜索离 `p` 最近的点，返回所在线段、弧长和距离
+    #[inline]
+    pub fn project(&self, p: (f32, f32), segment: usize) -> (usize, f32, f32) {
+        self.search(p, segment, segment + SEARCH)
+    }
+
+    /// 在整条路径上搜索离 `p` 最近的点，返回所在线段、弧长和距离
+    #[inline]
+    pub fn nearest(&self, p: (f32, f32)) -> (usize, f32, f32) {
+        self.search(p, 0, self.points.len())
+    }
+
+    fn search(&self, p: (f32, f32), segment: usize, end: usize) -> (usize, f32, f32) {
+        let end = end.min(self.points.len().saturating_sub(1));
+        let mut best = (segment, self.lengths[segment.min(end)], f32::INFINITY);
+        if self.points.len() == 1 {
+            let a = self.points[0];
+            best.2 = (a.0 - p.0).hypot(a.1 - p.1);
+        }
+        for i in segment..end {
+            let (a, b) = (self.points[i], self.points[i + 1]);
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let len2 = dx * dx + dy * dy;
+            let k = if len2 > 0.0 {
+                (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let d = (a.0 + dx * k - p.0).hypot(a.1 + dy * k - p.1);
+            if d < best.2 {
+                best = (i, self.lengths[i] + k * len2.sqrt(), d);
+            }
+        }
+        best
+    }
+}
+
+/// 按固定间隔报告进度
+#[derive(Default)]
+pub(crate) struct Progress {
+    reported: f32,
+}
+
+impl Progress {
+    /// 进度跨过下一个报告点时返回当前进度
+    pub fn report(&mut self, progress: f32) -> Option<f32> {
+        if progress >= self.reported + PROGRESS_STEP {
+            self.reported = (progress / PROGRESS_STEP).floor() * PROGRESS_STEP;
+            Some(progress)
+        } else {
+            None
+        }
+    }
+}
+
+/// 跟随路径的公共过程：超时、沿路径前进的距离、到达终点和进度报告
+pub(crate) struct Tracker {
+    path: Path,
+    pub tolerance: f32,
+    pub timeout: Option<Duration>,
+
+    /// 首次更新时确定的截止时间
+    deadline: Option<Option<Instant>>,
+    segment: usize,
+    travelled: f32,
+    progress: Progress,
+}
+
+impl Tracker {
+    pub fn new(points: Vec<(f32, f32)>) -> Self {
+        Self {
+            path: Path::new(points),
+            tolerance: TOLERANCE,
+            timeout: None,
+
+            deadline: None,
+            segment: 0,
+            travelled: 0.0,
+            progress: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 当前所在的线段
+    #[inline]
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    /// 已经走过的路径长度（m）
+    #[inline]
+    pub fn travelled(&self) -> f32 {
+        self.travelled
+    }
+
+    /// 用当前位姿更新进度，返回机器人坐标系下的终点和剩余距离。
+    ///
+    /// 只有一个点时从首次更新的位置驶向该点；路径为空、超时或到达终点时返回任务结果。
+    pub fn update(
+        &mut self,
+        time: Instant,
+        pose: Pose,
+    ) -> Result<((f32, f32), f32), AutopilotResult> {
+        let goal = self.path.end().ok_or(AutopilotResult::Finished)?;
+        if self.path.points().len() == 1 {
+            self.path = Path::new(vec![(pose.x, pose.y), goal]);
+        }
+        if let Some(timeout) = self.timeout {
+            // 截止时间溢出时视为不限时间
+            let deadline = *self
+                .deadline
+                .get_or_insert_with(|| time.checked_add(timeout));
+            if deadline.is_some_and(|deadline| time >= deadline) {
+                return Err(AutopilotResult::TimedOut);
+            }
+        }
+
+        let (segment, s, _) = self.path.project((pose.x, pose.y), self.segment);
+        if s >= self.travelled {
+            self.segment = segment;
+            self.travelled = s;
+        }
+        let goal = pose.inverse_transform(goal);
+        let distance = goal.0.hypot(goal.1);
+        if distance < self.tolerance {
+            return Err(AutopilotResult::Finished);
+        }
+        Ok((goal, (self.path.length() - self.travelled).max(distance)))
+    }
+
+    /// 每走过 10% 报告一次进度
+    pub fn progress(&mut self) -> Option<f32> {
+        let length = self.path.length();
+        if length <= 0.0 {
+            return None;
+        }
+        self.progress.report((self.travelled / length).min(1.0))
+    }
+}
@@ -0,0 +1,126 @@
+use pm1_control_model::{Odometry, Velocity};
+use std::{
+    f32::consts::{PI, TAU},
+    fmt::Display,
+};
+
+/// 里程计坐标系下的位姿
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+}
+
+impl Pose {
+    pub const ZERO: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        theta: 0.0,
+    };
+
+    /// 将机器人坐标系中的点变换到里程计坐标系
+    #[inline]
+    pub fn transform(&self, point: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.theta.sin_cos();
+        (
+            self.x + point.0 * cos - point.1 * sin,
+            self.y + point.0 * sin + point.1 * cos,
+        )
+    }
+
+    /// 将里程计坐标系中的点变换到机器人坐标系
+    #[inline]
+    pub fn inverse_transform(&self, point: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.theta.sin_cos();
+        let (dx, dy) = (point.0 - self.x, point.1 - self.y);
+        (dx * cos + dy * sin, -dx * sin + dy * cos)
+    }
+
+    /// 将以此位姿为原点的坐标系中的位姿 `other` 变换到里程计坐标系
+    #[inline]
+    pub fn compose(&self, other: &Pose) -> Pose {
+        let (x, y) = self.transform((other.x, other.y));
+        Pose {
+            x,
+            y,
+            theta: normalize(self.theta + other.theta),
+        }
+    }
+
+    /// 逆变换，满足 `p.compose(&p.inverse()) == Pose::ZERO`
+    #[inline]
+    pub fn inverse(&self) -> Pose {
+        let (x, y) = self.inverse_transform((0.0, 0.0));
+        Pose {
+            x,
+            y,
+            theta: normalize(-self.theta),
+        }
+    }
+
+    /// 到另一个位姿的平面距离
+    #[inline]
+    pub fn distance(&self, other: &Pose) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+impl From<&Odometry> for Pose {
+    /// 里程计累积的位姿，以开始累积处为原点
+    fn from(odometry: &Odometry) -> Self {
+        let translation = &odometry.pose.translation.vector;
+        Self {
+            x: translation[0],
+            y: translation[1],
+            theta: odometry.pose.rotation.angle(),
+        }
+    }
+}
+
+/// 从给定的原点开始累积里程计，得到里程计坐标系下的位姿
+#[derive(Clone)]
+pub(crate) struct Odometer {
+    origin: Pose,
+    odometry: Odometry,
+}
+
+impl Odometer {
+    #[inline]
+    pub fn new(origin: Pose) -> Self {
+        Self {
+            origin,
+            odometry: Odometry::ZERO,
+        }
+    }
+
+    /// 累积一个周期内的位移 `delta`，`delta.v` 为弧长，`delta.w` 为转角
+    #[inline]
+    pub fn push(&mut self, delta: Velocity) {
+        self.odometry += delta.to_odometry();
+    }
+
+    #[inline]
+    pub fn pose(&self) -> Pose {
+        self.origin.compose(&Pose::from(&self.odometry))
+    }
+}
+
+/// 将角度规范到 (-π, π]
+#[inline]
+pub(crate) fn normalize(angle: f32) -> f32 {
+    let angle = angle % TAU;
+    if angle > PI {
+        angle - TAU
+    } else if angle <= -PI {
+        angle + TAU
+    } else {
+        angle
+    }
+}
+
+impl Display for Pose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {}rad)", self.x, self.y, self.theta)
+    }
+}
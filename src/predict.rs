@@ -0,0 +1,176 @@
+use crate::{limits::Limiter, pose::Odometer, Pose, CONTROL_PERIOD, PM1};
+use pm1_control_model::{Optimizer, Physical, Pm1Model, Wheels};
+
+/// 降速时依次尝试的速度比例
+const SCALES: [f32; 4] = [0.75, 0.5, 0.25, 0.0];
+
+/// 底盘动力学参数，决定速度优化和推演的行为
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Dynamics {
+    /// 传给 `Optimizer::new` 的两个参数
+    pub optimizer: (f32, f32),
+    /// 后轮转向的角速度（rad/s）
+    pub rudder_rate: f32,
+}
+
+impl Default for Dynamics {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            optimizer: (0.5, 1.2),
+            rudder_rate: 3.0,
+        }
+    }
+}
+
+impl Dynamics {
+    /// 所有参数都是有限正数
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        [self.optimizer.0, self.optimizer.1, self.rudder_rate]
+            .iter()
+            .all(|x| x.is_finite() && *x > 0.0)
+    }
+
+    #[inline]
+    pub fn optimizer(&self) -> Optimizer {
+        Optimizer::new(self.optimizer.0, self.optimizer.1, CONTROL_PERIOD)
+    }
+}
+
+/// 轨迹预测器，将控制目标按控制周期向前推演，得到底盘的位姿和控制状态序列。
+///
+/// 与 `update_rudder` 中的控制过程一致：先优化速度，再施加运行时限制，
+/// 后轮以 `Dynamics::rudder_rate` 转向目标角度。
+/// 虚拟围栏、障碍物检查、模型预测控制和模型验证都使用这一预测；
+/// 不考虑运行时限制和转向速率的预测见 `PM1::trajectory_predictor`。
+#[derive(Clone)]
+pub struct ControlPredictor {
+    model: Pm1Model,
+    optimizer: Optimizer,
+    limiter: Limiter,
+    rudder_step: f32,
+    period: f32,
+
+    target: Physical,
+    current: Physical,
+    odometer: Odometer,
+}
+
+impl PM1 {
+    #[inline]
+    pub fn dynamics(&self) -> Dynamics {
+        self.dynamics
+    }
+
+    /// 更换动力学参数，同时重建优化器；参数不合法时保持不变并返回 `false`
+    #[inline]
+    pub fn set_dynamics(&mut self, dynamics: Dynamics) -> bool {
+        if !dynamics.is_valid() {
+            return false;
+        }
+        self.dynamics = dynamics;
+        self.optimizer = dynamics.optimizer();
+        self.profile = None;
+        true
+    }
+
+    /// 从当前位姿和控制状态开始推演当前的控制目标，
+    /// 使用当前的动力学参数和运行时限制
+    #[inline]
+    pub fn control_predictor(&self) -> ControlPredictor {
+        ControlPredictor::new(
+            self.model.clone(),
+            self.dynamics,
+            self.limiter.clone(),
+            self.target.1,
+            self.status.physical,
+            self.pose,
+        )
+    }
+}
+
+impl ControlPredictor {
+    pub(crate) fn new(
+        model: Pm1Model,
+        dynamics: Dynamics,
+        limiter: Limiter,
+        target: Physical,
+        current: Physical,
+        pose: Pose,
+    ) -> Self {
+        let period = CONTROL_PERIOD.as_secs_f32();
+        Self {
+            model,
+            optimizer: dynamics.optimizer(),
+            limiter,
+            rudder_step: dynamics.rudder_rate * period,
+            period,
+
+            target,
+            current,
+            odometer: Odometer::new(pose),
+        }
+    }
+
+    /// 更换推演的目标，从已经推演到的状态继续
+    #[inline]
+    pub fn set_target(&mut self, target: Physical) {
+        self.target = target;
+    }
+
+    #[inline]
+    pub fn with_target(mut self, target: Physical) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// 已经推演到的位姿
+    #[inline]
+    pub fn pose(&self) -> Pose {
+        self.odometer.pose()
+    }
+
+    /// 已经推演到的控制状态
+    #[inline]
+    pub fn current(&self) -> Physical {
+        self.current
+    }
+}
+
+/// 依次降低 `target` 的速度，返回首个满足 `safe` 的目标，最终速度降为零
+pub(crate) fn scale_down(target: Physical, mut safe: impl FnMut(Physical) -> bool) -> Physical {
+    SCALES
+        .iter()
+        .map(|k| Physical {
+            speed: target.speed * k,
+            rudder: target.rudder,
+        })
+        .find(|t| t.speed == 0.0 || safe(*t))
+        .unwrap()
+}
+
+impl Iterator for ControlPredictor {
+    type Item = (Pose, Physical);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut target = self.target;
+        if target.rudder.is_nan() {
+            target.rudder = self.current.rudder;
+        }
+        target.speed = self.optimizer.optimize_speed(target, self.current);
+        let target = self.limiter.limit(target, self.current, &self.model);
+        // 后轮追踪目标角度
+        let current = &mut self.current;
+        current.speed = target.speed;
+        current.rudder +=
+            (target.rudder - current.rudder).clamp(-self.rudder_step, self.rudder_step);
+        // 推进位姿
+        let Wheels { left, right } = self.model.physical_to_wheels(*current);
+        self.odometer.push(self.model.wheels_to_velocity(Wheels {
+            left: left * self.period,
+            right: right * self.period,
+        }));
+        Some((self.odometer.pose(), *current))
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{Dynamics, Limits, PM1};
+
+/// 与负载相关的一组参数，例如空载、满载、牵引各用一组
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Profile {
+    /// 速度优化、推演和诊断使用的动力学参数
+    pub dynamics: Dynamics,
+    /// 运行时限制
+    pub limits: Limits,
+}
+
+impl PM1 {
+    /// 添加或替换一组参数；替换当前选中的参数时立即生效。
+    ///
+    /// 动力学参数不合法时不添加并返回 `false`。
+    pub fn add_profile(&mut self, name: &str, profile: Profile) -> bool {
+        if !profile.dynamics.is_valid() {
+            return false;
+        }
+        self.profiles.insert(name.into(), profile);
+        if self.profile.as_deref() == Some(name) {
+            self.apply_profile(profile);
+        }
+        true
+    }
+
+    /// 移除一组参数，已应用的参数保持不变
+    pub fn remove_profile(&mut self, name: &str) -> Option<Profile> {
+        if self.profile.as_deref() == Some(name) {
+            self.profile = None;
+        }
+        self.profiles.remove(name)
+    }
+
+    /// 已添加的参数名称
+    #[inline]
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// 当前选中的参数名称，单独修改动力学参数或限制后不再视为选中
+    #[inline]
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// 切换到指定名称的参数，从下一个控制周期开始生效，名称不存在时返回 `false`
+    pub fn select_profile(&mut self, name: &str) -> bool {
+        match self.profiles.get(name).copied() {
+            Some(profile) => {
+                self.apply_profile(profile);
+                self.profile = Some(name.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn apply_profile(&mut self, profile: Profile) {
+        self.dynamics = profile.dynamics;
+        self.optimizer = profile.dynamics.optimizer();
+        self.limiter.set_limits(profile.limits);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    autopilot::{Autopilot, AutopilotResult, Step},
+    kinematics::{rudder_for_curvature, unit_velocity},
+    path::{profile, Tracker, DECELERATION, MIN_SPEED},
+    PM1,
+};
+use pm1_control_model::{Physical, Pm1Model};
+use std::{
+    f32::consts::FRAC_PI_2,
+    time::{Duration, Instant},
+};
+
+/// 默认的最小前视距离（m）
+const LOOKAHEAD: f32 = 0.5;
+/// 默认的前视时间（s），前视距离随速度增加
+const LOOKAHEAD_TIME: f32 = 1.0;
+/// 前视点在身后时原地转向的角速度（rad/s）
+const TURN_RATE: f32 = 0.5;
+
+/// 纯追踪路径跟随器。
+///
+/// 路径为里程计坐标系下的折线，底盘沿路径前进，
+/// 每个控制周期根据前视点求出曲率，再换算为后轮角度。
+/// 前视点在身后时先原地转向，直到前视点转到前方。
+pub struct PurePursuit {
+    tracker: Tracker,
+    speed: f32,
+    speeds: Vec<f32>,
+    lookahead: f32,
+    lookahead_time: f32,
+}
+
+impl PurePursuit {
+    /// 以最大速度 `speed`（m/s）跟随 `path`，只有一个点时从当前位置驶向该点
+    pub fn new(path: Vec<(f32, f32)>, speed: f32) -> Self {
+        Self {
+            tracker: Tracker::new(path),
+            speed: speed.abs(),
+            speeds: Vec::new(),
+            lookahead: LOOKAHEAD,
+            lookahead_time: LOOKAHEAD_TIME,
+        }
+    }
+
+    /// 修改前视距离：`min` 为最小前视距离（m），`time` 为前视时间（s）
+    #[inline]
+    pub fn with_lookahead(mut self, min: f32, time: f32) -> Self {
+        self.lookahead = min;
+        self.lookahead_time = time;
+        self
+    }
+
+    /// 为路径的每一段设置速度上限（m/s），第 `i` 个值对应从第 `i` 个点开始的一段
+    #[inline]
+    pub fn with_speeds(mut self, speeds: Vec<f32>) -> Self {
+        self.speeds = speeds;
+        self
+    }
+
+    /// 修改终点容差（m）
+    #[inline]
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tracker.tolerance = tolerance;
+        self
+    }
+
+    /// 设置超时，默认不限时间
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.tracker.timeout = Some(timeout);
+        self
+    }
+
+    /// 路径总长度（m）
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.tracker.path().length()
+    }
+
+    /// 已经走过的路径长度（m）
+    #[inline]
+    pub fn travelled(&self) -> f32 {
+        self.tracker.travelled()
+    }
+}
+
+impl Autopilot for PurePursuit {
+    fn step(&mut self, time: Instant, pm1: &PM1) -> Step {
+        let pose = pm1.pose();
+        let ((gx, _), remaining) = match self.tracker.update(time, pose) {
+            Ok(tracking) => tracking,
+            Err(result) => return Step::Finish(result),
+        };
+        if gx < 0.0 && self.length() - self.travelled() < self.tracker.tolerance {
+            // 已越过终点但偏离超过容差，掉头重新驶向终点只会绕圈
+            return Step::Finish(AutopilotResult::Failed);
+        }
+
+        let lookahead = self
+            .lookahead
+            .max(self.lookahead_time * pm1.status().physical.speed.abs());
+        let (x, y) = pose.inverse_transform(
+            self.tracker
+                .path()
+                .point_at(self.tracker.travelled() + lookahead),
+        );
+        let max = self
+            .speeds
+            .get(self.tracker.segment())
+            .map_or(self.speed, |s| s.min(self.speed));
+        Step::Drive(steer(
+            &pm1.model,
+            (x, y),
+            profile(remaining, max, DECELERATION, MIN_SPEED),
+        ))
+    }
+
+    #[inline]
+    fn progress(&mut self) -> Option<f32> {
+        self.tracker.progress()
+    }
+}
+
+/// 以速度 `speed` 驶向机器人坐标系中的前视点 `target`。
+///
+/// 前视点在身后时纯追踪的曲率会使底盘背离前视点，此时原地向前视点一侧转向。
+fn steer(model: &Pm1Model, target: (f32, f32), speed: f32) -> Physical {
+    let (x, y) = target;
+    if x < 0.0 {
+        let unit = unit_velocity(model, FRAC_PI_2);
+        let direction = if y < 0.0 { -1.0 } else { 1.0 };
+        return Physical {
+            speed: TURN_RATE / unit.w.abs() * direction * unit.w.signum(),
+            rudder: FRAC_PI_2,
+        };
+    }
+    let d2 = x * x + y * y;
+    let curvature = if d2 > 0.0 { 2.0 * y / d2 } else { 0.0 };
+    Physical {
+        speed,
+        rudder: rudder_for_curvature(model, curvature),
+    }
+}
+
+impl PM1 {
+    /// 以最大速度 `speed` 跟随里程计坐标系下的路径，不阻塞。
+    ///
+    /// 每走过 10% 产生一次 `PM1Event::AutopilotProgress` 事件，
+    /// 到达终点时产生 `PM1Event::AutopilotFinished` 事件。
+    #[inline]
+    pub fn follow_path(&mut self, path: Vec<(f32, f32)>, speed: f32) {
+        self.start_autopilot(PurePursuit::new(path, speed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pose;
+
+    /// 按 `steer` 的输出运动时底盘的线速度和角速度
+    fn motion(model: &Pm1Model, target: (f32, f32)) -> (f32, f32) {
+        let Physical { speed, rudder } = steer(model, target, 0.5);
+        let unit = unit_velocity(model, rudder);
+        (unit.v * speed, unit.w * speed)
+    }
+
+    #[test]
+    fn drive_toward_point_ahead() {
+        let model = Pm1Model::default();
+        let (v, w) = motion(&model, (1.0, 0.0));
+        assert!(v > 0.0);
+        assert!(w.abs() < 1e-3);
+        let (v, w) = motion(&model, (1.0, 0.5));
+        assert!(v > 0.0 && w > 0.0);
+    }
+
+    #[test]
+    fn turn_in_place_toward_point_behind() {
+        let model = Pm1Model::default();
+        // 终点在正后方
+        let (v, w) = motion(&model, (-1.0, 0.0));
+        assert!(v.abs() < 1e-3);
+        assert!((w - TURN_RATE).abs() < 1e-3);
+        let (v, w) = motion(&model, (-1.0, -0.2));
+        assert!(v.abs() < 1e-3);
+        assert!((w + TURN_RATE).abs() < 1e-3);
+    }
+
+    #[test]
+    fn reach_goal_behind_start() {
+        let model = Pm1Model::default();
+        let start = Instant::now();
+        let mut tracker = Tracker::new(vec![(-1.0, 0.0)]);
+        let mut pose = Pose::ZERO;
+        for i in 0..1000 {
+            let time = start + Duration::from_millis(40 * i);
+            let remaining = match tracker.update(time, pose) {
+                Ok((_, remaining)) => remaining,
+                Err(result) => {
+                    assert_eq!(result, AutopilotResult::Finished);
+                    return;
+                }
+            };
+            let target =
+                pose.inverse_transform(tracker.path().point_at(tracker.travelled() + LOOKAHEAD));
+            let Physical { speed, rudder } = steer(
+                &model,
+                target,
+                profile(remaining, 0.5, DECELERATION, MIN_SPEED),
+            );
+            let unit = unit_velocity(&model, rudder);
+            // 一个控制周期内的位移
+            let (s, a) = (unit.v * speed * 0.04, unit.w * speed * 0.04);
+            pose = pose.compose(&Pose {
+                x: s * (a / 2.0).cos(),
+                y: s * (a / 2.0).sin(),
+                theta: a,
+            });
+        }
+        panic!("did not reach the goal: {pose}");
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    autopilot::{Autopilot, AutopilotResult, Step},
+    PM1,
+};
+use pm1_control_model::Physical;
+use std::{
+    f32::consts::TAU,
+    time::{Duration, Instant},
+};
+
+/// 按时间表依次执行的控制目标。
+///
+/// 时间从任务开始后的第一个控制周期起算，每个目标从其开始时间一直保持到下一个目标，
+/// 第一个目标之前保持静止，到达总时长后结束。
+/// 目标在控制循环中切换，时间精度为一个控制周期。
+pub struct Schedule {
+    commands: Vec<(Duration, Physical)>,
+    duration: Duration,
+    start: Option<Instant>,
+}
+
+impl Schedule {
+    pub fn new(mut commands: Vec<(Duration, Physical)>, duration: Duration) -> Self {
+        commands.sort_by_key(|(t, _)| *t);
+        Self {
+            commands,
+            duration,
+            start: None,
+        }
+    }
+
+    /// 阶跃输入：先保持 `before`，在 `at` 时刻切换为 `after`
+    #[inline]
+    pub fn step_input(before: Physical, after: Physical, at: Duration, duration: Duration) -> Self {
+        Self::new(vec![(Duration::ZERO, before), (at, after)], duration)
+    }
+}
+
+impl Autopilot for Schedule {
+    fn step(&mut self, time: Instant, _: &PM1) -> Step {
+        let elapsed = time - *self.start.get_or_insert(time);
+        if elapsed >= self.duration {
+            return Step::Finish(AutopilotResult::Finished);
+        }
+        let i = self.commands.partition_point(|(t, _)| *t <= elapsed);
+        Step::Drive(match i {
+            0 => Physical::ZERO,
+            _ => self.commands[i - 1].1,
+        })
+    }
+}
+
+/// 由时间的函数给出的连续控制目标。
+///
+/// 函数的参数为自任务开始后的第一个控制周期起经过的时间，返回 `None` 时结束。
+pub struct Waveform<F> {
+    f: F,
+    start: Option<Instant>,
+}
+
+impl<F> Waveform<F>
+where
+    F: FnMut(Duration) -> Option<Physical> + Send,
+{
+    #[inline]
+    pub fn new(f: F) -> Self {
+        Self { f, start: None }
+    }
+}
+
+/// 速度扫频输入：后轮保持 `rudder`，速度在 `offset` 附近以幅值 `amplitude` 正弦变化，
+/// 频率在 `duration` 内从 `f0` 线性增加到 `f1`（Hz）
+pub fn chirp(
+    offset: f32,
+    amplitude: f32,
+    (f0, f1): (f32, f32),
+    rudder: f32,
+    duration: Duration,
+) -> Waveform<impl FnMut(Duration) -> Option<Physical> + Send> {
+    let total = duration.as_secs_f32();
+    Waveform::new(move |t: Duration| {
+        let t = t.as_secs_f32();
+        if t >= total {
+            return None;
+        }
+        let phase = TAU * (f0 * t + (f1 - f0) * t * t / (2.0 * total));
+        Some(Physical {
+            speed: offset + amplitude * phase.sin(),
+            rudder,
+        })
+    })
+}
+
+impl<F> Autopilot for Waveform<F>
+where
+    F: FnMut(Duration) -> Option<Physical> + Send,
+{
+    fn step(&mut self, time: Instant, _: &PM1) -> Step {
+        let elapsed = time - *self.start.get_or_insert(time);
+        match (self.f)(elapsed) {
+            Some(target) => Step::Drive(target),
+            None => Step::Finish(AutopilotResult::Finished),
+        }
+    }
+}
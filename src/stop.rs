@@ -0,0 +1,119 @@
+use pm1_control_model::Wheels;
+use std::time::Duration;
+
+/// 停止方式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopMode {
+    /// 立即停止发送控制，由电机控制器自行释放
+    Coast,
+    /// 经过优化器平滑减速至停止
+    Decelerate,
+    /// 以零速度制动，直到编码器反馈静止
+    Brake,
+    /// 制动至静止后利用编码器反馈保持静止时的位置
+    Hold,
+}
+
+/// 触发停止的原因
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    /// 控制目标超时
+    Timeout,
+    /// 急停开关断开
+    PowerSwitch,
+    /// 调用 `PM1::stop` 主动停止
+    Command,
+    /// 电量低于 `BatteryPolicy::stop`
+    Battery,
+    /// 诊断出后轮故障，需调用 `PM1::set_stop_on_rudder_fault` 开启
+    RudderFault,
+}
+
+/// 为每种停止原因选择停止方式。
+///
+/// 默认的策略与旧版行为一致：超时减速，急停滑行，主动停止和低电量停止减速，
+/// 后轮故障时制动。
+#[derive(Clone, Copy, Debug)]
+pub struct StopPolicy {
+    pub timeout: StopMode,
+    pub power_switch: StopMode,
+    pub command: StopMode,
+    pub battery: StopMode,
+    pub rudder_fault: StopMode,
+}
+
+impl Default for StopPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            timeout: StopMode::Decelerate,
+            power_switch: StopMode::Coast,
+            command: StopMode::Decelerate,
+            battery: StopMode::Decelerate,
+            rudder_fault: StopMode::Brake,
+        }
+    }
+}
+
+impl StopPolicy {
+    #[inline]
+    pub fn mode(&self, reason: StopReason) -> StopMode {
+        match reason {
+            StopReason::Timeout => self.timeout,
+            StopReason::PowerSwitch => self.power_switch,
+            StopReason::Command => self.command,
+            StopReason::Battery => self.battery,
+            StopReason::RudderFault => self.rudder_fault,
+        }
+    }
+}
+
+/// 编码器超过此时长没有变化即认为底盘静止，覆盖至少两次里程计询问
+pub(crate) const STANDSTILL_TIMEOUT: Duration = Duration::from_millis(200);
+/// 保持位置的比例增益（rad/s 每 rad）
+const HOLD_GAIN: f32 = 4.0;
+/// 保持位置时的最大轮速（rad/s）
+const HOLD_SPEED: f32 = 2.0;
+/// 小于此偏差（rad）不做修正，避免在零点附近抖动
+const HOLD_DEADBAND: f32 = 0.02;
+
+/// 根据轮子相对保持原点的转角计算修正轮速
+pub(crate) fn hold_wheels(origin: (f32, f32), position: (f32, f32)) -> Wheels {
+    #[inline]
+    fn correct(error: f32) -> f32 {
+        if error.abs() < HOLD_DEADBAND {
+            0.0
+        } else {
+            (-HOLD_GAIN * error).clamp(-HOLD_SPEED, HOLD_SPEED)
+        }
+    }
+
+    Wheels {
+        left: correct(position.0 - origin.0),
+        right: correct(position.1 - origin.1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hold_within_deadband() {
+        let wheels = hold_wheels((1.0, -1.0), (1.01, -1.01));
+        assert_eq!((wheels.left, wheels.right), (0.0, 0.0));
+    }
+
+    #[test]
+    fn hold_drives_back_to_origin() {
+        let wheels = hold_wheels((0.0, 0.0), (0.1, -0.2));
+        assert!((wheels.left + HOLD_GAIN * 0.1).abs() < 1e-6);
+        assert!((wheels.right - HOLD_GAIN * 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hold_speed_is_limited() {
+        let wheels = hold_wheels((0.0, 0.0), (10.0, -10.0));
+        assert_eq!((wheels.left, wheels.right), (-HOLD_SPEED, HOLD_SPEED));
+    }
+}
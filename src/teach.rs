@@ -0,0 +1,138 @@
+use crate::{Pose, PurePursuit, PM1};
+use pm1_control_model::Physical;
+use std::{fs, io, path::Path};
+
+/// 记录路线时相邻两点的最小间距（m）
+const SPACING: f32 = 0.05;
+/// 复现时每段路线的最低速度（m/s），避免在示教时起步缓慢的位置停住
+const MIN_SPEED: f32 = 0.1;
+
+/// 路线上的一点：里程计位姿和当时底盘的状态
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RoutePoint {
+    pub pose: Pose,
+    pub physical: Physical,
+}
+
+/// 示教记录的路线。
+///
+/// 文本格式每行一点：`x y theta 速度 后轮角度`。
+#[derive(Clone, Default, Debug)]
+pub struct Route {
+    pub points: Vec<RoutePoint>,
+}
+
+impl Route {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = self
+            .points
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} {} {} {} {}\n",
+                    p.pose.x, p.pose.y, p.pose.theta, p.physical.speed, p.physical.rudder
+                )
+            })
+            .collect::<String>();
+        fs::write(path, text)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut points = Vec::new();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let values = line
+                .split_whitespace()
+                .map(|s| s.parse::<f32>().ok())
+                .collect::<Option<Vec<_>>>();
+            match values.as_deref() {
+                Some(&[x, y, theta, speed, rudder]) => points.push(RoutePoint {
+                    pose: Pose { x, y, theta },
+                    physical: Physical { speed, rudder },
+                }),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid route: {}", line),
+                    ))
+                }
+            }
+        }
+        Ok(Self { points })
+    }
+
+    /// 路线总长度（m）
+    pub fn length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|w| w[0].pose.distance(&w[1].pose))
+            .sum()
+    }
+}
+
+impl PM1 {
+    /// 开始记录示教路线，之前未结束的记录被丢弃
+    #[inline]
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Route {
+            points: vec![RoutePoint {
+                pose: self.pose,
+                physical: self.status.physical,
+            }],
+        });
+    }
+
+    /// 结束记录，返回记录的路线
+    #[inline]
+    pub fn stop_recording(&mut self) -> Option<Route> {
+        self.recording.take()
+    }
+
+    #[inline]
+    pub fn recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// 从当前位置开始复现路线，不阻塞。
+    ///
+    /// 底盘应放在示教时的起点（`reverse` 时为终点）并朝向相同，
+    /// 路线以此为基准变换到当前里程计坐标系。
+    /// `reverse` 为 `true` 时从终点沿原路返回起点，路线的开头在身后，底盘先原地掉头再前进。
+    /// 每段的速度不超过示教时的速度，也不超过 `speed`。
+    pub fn replay_route(&mut self, route: &Route, reverse: bool, speed: f32) {
+        let mut points = route.points.clone();
+        if reverse {
+            points.reverse();
+        }
+        let origin = match points.first() {
+            Some(p) => p.pose,
+            None => return,
+        };
+        let pose = self.pose;
+        let path = points
+            .iter()
+            .map(|p| pose.transform(origin.inverse_transform((p.pose.x, p.pose.y))))
+            .collect();
+        let speeds = points
+            .iter()
+            .map(|p| p.physical.speed.abs().max(MIN_SPEED))
+            .collect();
+        self.start_autopilot(PurePursuit::new(path, speed).with_speeds(speeds));
+    }
+
+    /// 里程计更新后记录路线
+    pub(crate) fn record_route(&mut self) {
+        if let Some(route) = &mut self.recording {
+            let far = route
+                .points
+                .last()
+                .is_none_or(|p| p.pose.distance(&self.pose) >= SPACING);
+            if far {
+                route.points.push(RoutePoint {
+                    pose: self.pose,
+                    physical: self.status.physical,
+                });
+            }
+        }
+    }
+}
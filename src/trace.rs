@@ -0,0 +1,180 @@
+use crate::PM1;
+use pm1_control_model::{Physical, Wheels};
+use std::{
+    io::{self, BufRead, Write},
+    time::Instant,
+};
+
+/// 控制过程记录中的一条。
+///
+/// 文本格式每行一条，时间为自开始记录起的秒数：
+///
+/// - `C 时间 目标速度 目标角度 发送速度 发送角度 实际角度`
+/// - `W 时间 左轮转角 右轮转角`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TraceRecord {
+    /// 一个控制周期：送入优化器的目标、发往底盘的控制量和测得的后轮角度
+    Control {
+        time: f32,
+        target: Physical,
+        sent: Physical,
+        rudder: f32,
+    },
+    /// 一次里程计增量
+    Wheels { time: f32, wheels: Wheels },
+}
+
+impl TraceRecord {
+    #[inline]
+    pub fn time(&self) -> f32 {
+        match self {
+            Self::Control { time, .. } | Self::Wheels { time, .. } => *time,
+        }
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::Control {
+                time,
+                target,
+                sent,
+                rudder,
+            } => writeln!(
+                w,
+                "C {} {} {} {} {} {}",
+                time, target.speed, target.rudder, sent.speed, sent.rudder, rudder
+            ),
+            Self::Wheels { time, wheels } => {
+                writeln!(w, "W {} {} {}", time, wheels.left, wheels.right)
+            }
+        }
+    }
+
+    /// 解析一行，格式错误时返回 `None`
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let values = fields
+            .map(|s| s.parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match (kind, values.as_slice()) {
+            ("C", &[time, target_speed, target_rudder, speed, sent_rudder, rudder]) => {
+                Some(Self::Control {
+                    time,
+                    target: Physical {
+                        speed: target_speed,
+                        rudder: target_rudder,
+                    },
+                    sent: Physical {
+                        speed,
+                        rudder: sent_rudder,
+                    },
+                    rudder,
+                })
+            }
+            ("W", &[time, left, right]) => Some(Self::Wheels {
+                time,
+                wheels: Wheels { left, right },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 读取记录文件，跳过空行，遇到格式错误时报错
+pub fn read_trace(reader: impl BufRead) -> io::Result<Vec<TraceRecord>> {
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        records.push(TraceRecord::parse(line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid trace: {}", line),
+            )
+        })?);
+    }
+    Ok(records)
+}
+
+/// 正在进行的记录
+pub(crate) struct Tracer {
+    start: Instant,
+    writer: Box<dyn Write + Send>,
+}
+
+impl PM1 {
+    /// 开始记录控制过程，写入失败时自动停止
+    #[inline]
+    pub fn start_trace(&mut self, writer: Box<dyn Write + Send>) {
+        self.tracer = Some(Tracer {
+            start: Instant::now(),
+            writer,
+        });
+    }
+
+    #[inline]
+    pub fn stop_trace(&mut self) {
+        if let Some(mut tracer) = self.tracer.take() {
+            let _ = tracer.writer.flush();
+        }
+    }
+
+    pub(crate) fn trace(&mut self, time: Instant, f: impl FnOnce(f32) -> TraceRecord) {
+        if let Some(tracer) = &mut self.tracer {
+            let record = f(time.saturating_duration_since(tracer.start).as_secs_f32());
+            if record.write_to(&mut tracer.writer).is_err() {
+                self.tracer = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let records = [
+            TraceRecord::Control {
+                time: 0.04,
+                target: Physical {
+                    speed: 0.5,
+                    rudder: -0.25,
+                },
+                sent: Physical {
+                    speed: 0.125,
+                    rudder: -0.1,
+                },
+                rudder: -0.0625,
+            },
+            TraceRecord::Wheels {
+                time: 0.08,
+                wheels: Wheels {
+                    left: 0.015625,
+                    right: -1e-7,
+                },
+            },
+        ];
+        let mut text = Vec::new();
+        for record in &records {
+            record.write_to(&mut text).unwrap();
+        }
+        // 空行被跳过
+        text.extend_from_slice(b"\n");
+        assert_eq!(read_trace(text.as_slice()).unwrap(), records);
+    }
+
+    #[test]
+    fn reject_invalid_lines() {
+        for line in ["", "X 0 1 2", "C 0 1 2 3 4", "W 0 1", "W 0 1 a"] {
+            assert_eq!(TraceRecord::parse(line), None, "{:?}", line);
+        }
+        let e = read_trace("W 0 1 2\nW 0 1\n".as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
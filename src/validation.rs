@@ -0,0 +1,314 @@
+use crate::{
+    limits::Limiter,
+    pose::{normalize, Odometer},
+    ControlPredictor, Dynamics, Limits, Pose, TraceRecord, CONTROL_PERIOD, PM1,
+};
+use pm1_control_model::{Physical, Pm1Model};
+use std::{collections::VecDeque, fmt::Display, time::Instant};
+
+/// 目标变化小于此值时认为预测仍然有效
+const TARGET_TOLERANCE: f32 = 0.01;
+
+/// 一类工况下预测误差的统计
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ErrorStats {
+    pub count: usize,
+    position: f64,
+    heading: f64,
+    /// 最大位置误差（m）
+    pub max_position: f32,
+}
+
+impl ErrorStats {
+    /// 位置误差的均方根（m）
+    #[inline]
+    pub fn position_rmse(&self) -> f32 {
+        (self.position / self.count.max(1) as f64).sqrt() as f32
+    }
+
+    /// 航向误差的均方根（rad）
+    #[inline]
+    pub fn heading_rmse(&self) -> f32 {
+        (self.heading / self.count.max(1) as f64).sqrt() as f32
+    }
+
+    fn push(&mut self, position: f32, heading: f32) {
+        self.count += 1;
+        self.position += (position as f64).powi(2);
+        self.heading += (heading as f64).powi(2);
+        self.max_position = self.max_position.max(position);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.position += other.position;
+        self.heading += other.heading;
+        self.max_position = self.max_position.max(other.max_position);
+    }
+}
+
+/// 预测位姿与里程计位姿的比较结果，按预测时长、目标速度和目标角度分类
+#[derive(Clone, Default, Debug)]
+pub struct ValidationReport {
+    stats: [[[ErrorStats; 4]; 4]; 3],
+}
+
+impl ValidationReport {
+    /// 预测时长（控制周期数）
+    pub const HORIZONS: [usize; 3] = [5, 10, 25];
+    /// 目标速度绝对值（m/s）的分类上界，超过最后一个值的归入最后一类
+    pub const SPEED_BUCKETS: [f32; 3] = [0.2, 0.5, 1.0];
+    /// 目标角度绝对值（rad）的分类上界，超过最后一个值的归入最后一类
+    pub const RUDDER_BUCKETS: [f32; 3] = [0.1, 0.5, 1.0];
+
+    /// 指定预测时长、速度分类和角度分类的统计
+    #[inline]
+    pub fn get(&self, horizon: usize, speed: usize, rudder: usize) -> ErrorStats {
+        self.stats[horizon][speed][rudder]
+    }
+
+    /// 指定预测时长下所有工况的统计
+    pub fn horizon(&self, horizon: usize) -> ErrorStats {
+        let mut result = ErrorStats::default();
+        self.stats[horizon]
+            .iter()
+            .flatten()
+            .for_each(|s| result.merge(s));
+        result
+    }
+
+    #[inline]
+    fn bucket(bounds: &[f32; 3], value: f32) -> usize {
+        bounds
+            .iter()
+            .position(|b| value.abs() < *b)
+            .unwrap_or(bounds.len())
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[inline]
+        fn range(bounds: &[f32; 3], i: usize) -> String {
+            match i {
+                0 => format!("<{}", bounds[0]),
+                _ if i == bounds.len() => format!(">={}", bounds[i - 1]),
+                _ => format!("{}~{}", bounds[i - 1], bounds[i]),
+            }
+        }
+        writeln!(
+            f,
+            "horizon(s) speed rudder count pos_rmse(m) pos_max(m) head_rmse(rad)"
+        )?;
+        for (h, steps) in Self::HORIZONS.iter().enumerate() {
+            let seconds = (CONTROL_PERIOD * *steps as u32).as_secs_f32();
+            let total = self.horizon(h);
+            writeln!(
+                f,
+                "{} all all {} {} {} {}",
+                seconds,
+                total.count,
+                total.position_rmse(),
+                total.max_position,
+                total.heading_rmse()
+            )?;
+            for s in 0..=Self::SPEED_BUCKETS.len() {
+                for r in 0..=Self::RUDDER_BUCKETS.len() {
+                    let stats = self.get(h, s, r);
+                    if stats.count > 0 {
+                        writeln!(
+                            f,
+                            "{} {} {} {} {} {} {}",
+                            seconds,
+                            range(&Self::SPEED_BUCKETS, s),
+                            range(&Self::RUDDER_BUCKETS, r),
+                            stats.count,
+                            stats.position_rmse(),
+                            stats.max_position,
+                            stats.heading_rmse()
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 一次尚未完成比较的预测
+struct Pending {
+    start: f32,
+    target: Physical,
+    predicted: Vec<Pose>,
+    speed: usize,
+    rudder: usize,
+    next: usize,
+}
+
+/// 每个控制周期用 `ControlPredictor` 按当前目标推演一次，在里程计更新时与实际位姿比较。
+///
+/// 目标在预测时长内发生变化的预测被丢弃，因此报告只反映模型本身的误差。
+/// 时间为自开始验证起的秒数。
+#[derive(Default)]
+pub(crate) struct Validator {
+    pending: VecDeque<Pending>,
+    pub report: ValidationReport,
+}
+
+impl Validator {
+    /// 记录一个控制周期，`target` 为 `None` 表示没有按目标控制
+    pub fn control(&mut self, time: f32, target: Option<(Physical, ControlPredictor)>) {
+        let (target, predictor) = match target {
+            Some(t) => t,
+            None => {
+                self.pending.clear();
+                return;
+            }
+        };
+        // 多推演几个周期，容忍里程计更新的延迟
+        let steps = ValidationReport::HORIZONS[ValidationReport::HORIZONS.len() - 1] + 3;
+        // 丢弃目标已变化或迟迟等不到里程计的预测
+        let expire = (CONTROL_PERIOD * steps as u32).as_secs_f32();
+        self.pending.retain(|p| {
+            time < p.start + expire
+                && (p.target.speed - target.speed).abs() < TARGET_TOLERANCE
+                && (p.target.rudder - target.rudder).abs() < TARGET_TOLERANCE
+        });
+        self.pending.push_back(Pending {
+            start: time,
+            target,
+            predicted: predictor.take(steps).map(|(pose, _)| pose).collect(),
+            speed: ValidationReport::bucket(&ValidationReport::SPEED_BUCKETS, target.speed),
+            rudder: ValidationReport::bucket(&ValidationReport::RUDDER_BUCKETS, target.rudder),
+            next: 0,
+        });
+    }
+
+    /// 用里程计位姿检查到期的预测
+    pub fn odometry(&mut self, time: f32, pose: Pose) {
+        let period = CONTROL_PERIOD.as_secs_f32();
+        for p in self.pending.iter_mut() {
+            let elapsed = ((time - p.start) / period).round().max(0.0) as usize;
+            while let Some(steps) = ValidationReport::HORIZONS.get(p.next) {
+                if elapsed < *steps {
+                    break;
+                }
+                let predicted = p.predicted[elapsed.min(p.predicted.len()) - 1];
+                self.report.stats[p.next][p.speed][p.rudder].push(
+                    predicted.distance(&pose),
+                    normalize(predicted.theta - pose.theta).abs(),
+                );
+                p.next += 1;
+            }
+        }
+        self.pending
+            .retain(|p| p.next < ValidationReport::HORIZONS.len());
+    }
+}
+
+/// 离线回放控制过程记录，验证给定参数下 `ControlPredictor` 的预测。
+///
+/// `model` 应使用采集时底盘的校准参数；
+/// 记录中没有运行时限制，需要由调用者提供与采集时一致的 `limits`。
+pub fn validate(
+    records: &[TraceRecord],
+    model: &Pm1Model,
+    dynamics: Dynamics,
+    limits: Limits,
+) -> ValidationReport {
+    let mut limiter = Limiter::new(CONTROL_PERIOD);
+    limiter.set_limits(limits);
+    let mut validator = Validator::default();
+    let mut odometer = Odometer::new(Pose::ZERO);
+    let mut speed = 0.0;
+    let mut last_wheels = None;
+    for record in records {
+        match *record {
+            TraceRecord::Wheels { time, wheels } => {
+                let delta = model.wheels_to_velocity(wheels);
+                odometer.push(delta);
+                if let Some(last) = last_wheels.replace(time) {
+                    if time > last {
+                        speed = delta.v / (time - last);
+                    }
+                }
+                validator.odometry(time, odometer.pose());
+            }
+            TraceRecord::Control {
+                time,
+                target,
+                sent,
+                rudder,
+            } => {
+                let current = Physical { speed, rudder };
+                let predictor = ControlPredictor::new(
+                    model.clone(),
+                    dynamics,
+                    limiter.clone(),
+                    target,
+                    current,
+                    odometer.pose(),
+                );
+                validator.control(time, Some((target, predictor)));
+                // 发出的控制量已满足限制，用它推进限制器的状态
+                limiter.limit(sent, current, model);
+            }
+        }
+    }
+    validator.report
+}
+
+impl PM1 {
+    /// 开始在线验证 `control_predictor` 的预测，清除之前的统计
+    #[inline]
+    pub fn start_validation(&mut self) {
+        self.validator = Some((Instant::now(), Default::default()));
+    }
+
+    /// 停止在线验证，返回最终的统计
+    #[inline]
+    pub fn stop_validation(&mut self) -> Option<ValidationReport> {
+        self.validator.take().map(|(_, v)| v.report)
+    }
+
+    /// 在线验证的当前统计
+    #[inline]
+    pub fn validation(&self) -> Option<&ValidationReport> {
+        self.validator.as_ref().map(|(_, v)| &v.report)
+    }
+
+    /// 记录一个控制周期，`target` 为经过安全处理、尚未优化的目标
+    pub(crate) fn validate_control(
+        &mut self,
+        time: Instant,
+        target: Option<Physical>,
+        current: Physical,
+    ) {
+        if self.validator.is_none() {
+            return;
+        }
+        let target = target.map(|target| {
+            let predictor = ControlPredictor::new(
+                self.model.clone(),
+                self.dynamics,
+                self.limiter.clone(),
+                target,
+                current,
+                self.pose,
+            );
+            (target, predictor)
+        });
+        let (start, validator) = self.validator.as_mut().unwrap();
+        validator.control(time.saturating_duration_since(*start).as_secs_f32(), target);
+    }
+
+    /// 里程计更新后检查到期的预测
+    pub(crate) fn validate_odometry(&mut self, time: Instant) {
+        if let Some((start, validator)) = &mut self.validator {
+            validator.odometry(
+                time.saturating_duration_since(*start).as_secs_f32(),
+                self.pose,
+            );
+        }
+    }
+}